@@ -0,0 +1,357 @@
+//! Formatting of access log lines, in the Apache Common and Combined log formats, as JSON lines or
+//! following a custom template.
+
+use crate::request::Request;
+use crate::status::StatusCode;
+use crate::time::{format_clf_date, format_iso8601};
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// What a single request looked like to the server, everything an access log line is made of.
+pub struct AccessLogEntry<'a> {
+    /// The address of the client, if the request came in over a socket.
+    pub client: Option<IpAddr>,
+    /// When the request was received.
+    pub time: SystemTime,
+    /// `None` if the request couldn't be parsed.
+    pub request: Option<&'a Request>,
+    pub status: StatusCode,
+    /// The number of bytes in the body of the response.
+    pub bytes_sent: u64,
+    /// The time it took to handle the request.
+    pub latency: Duration,
+}
+
+impl AccessLogEntry<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.request?.headers.get(name)
+    }
+
+    /// The request line, e.g. `GET /index.html HTTP/1.1`.
+    fn request_line(&self) -> Option<String> {
+        let request = self.request?;
+        let version: &str = request.version.into();
+
+        Some(format!("{} {} {version}", request.method, request.target))
+    }
+}
+
+/// A value which can be used in custom log templates as `$name`. The names are the same as in
+/// nginx.
+#[derive(Clone, Debug, PartialEq)]
+enum Variable {
+    RemoteAddr,
+    TimeLocal,
+    TimeIso8601,
+    Request,
+    RequestMethod,
+    Uri,
+    QueryString,
+    ServerProtocol,
+    Status,
+    BodyBytesSent,
+    RequestTime,
+    /// `$http_user_agent` is the value of the `User-Agent` request header.
+    Header(String),
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "remote_addr" => Self::RemoteAddr,
+            "time_local" => Self::TimeLocal,
+            "time_iso8601" => Self::TimeIso8601,
+            "request" => Self::Request,
+            "request_method" => Self::RequestMethod,
+            "uri" => Self::Uri,
+            "query_string" | "args" => Self::QueryString,
+            "server_protocol" => Self::ServerProtocol,
+            "status" => Self::Status,
+            "body_bytes_sent" => Self::BodyBytesSent,
+            "request_time" => Self::RequestTime,
+            _ => match name.strip_prefix("http_") {
+                Some(header) if !header.is_empty() => Self::Header(header.replace('_', "-")),
+                _ => return None,
+            },
+        })
+    }
+
+    /// Writes the value to `line`, or `-` if there's none.
+    fn write(&self, entry: &AccessLogEntry, line: &mut String) {
+        let request = entry.request;
+
+        let value = match self {
+            Self::RemoteAddr => entry.client.map(|client| client.to_string()),
+            Self::TimeLocal => Some(format_clf_date(entry.time)),
+            Self::TimeIso8601 => Some(format_iso8601(entry.time)),
+            Self::Request => entry.request_line(),
+            Self::RequestMethod => request.map(|request| request.method.to_string()),
+            Self::Uri => request.map(|request| request.path.clone()),
+            Self::QueryString => request.and_then(|request| request.query.clone()),
+            Self::ServerProtocol => request.map(|request| {
+                let version: &str = request.version.into();
+                version.to_string()
+            }),
+            Self::Status => Some(entry.status.to_string()),
+            Self::BodyBytesSent => Some(entry.bytes_sent.to_string()),
+            Self::RequestTime => Some(format!(
+                "{}.{:03}",
+                entry.latency.as_secs(),
+                entry.latency.subsec_millis()
+            )),
+            Self::Header(name) => entry.header(name).map(str::to_string),
+        };
+
+        match value {
+            Some(value) => escape_into(&value, line),
+            None => line.push('-'),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Variable(Variable),
+}
+
+/// A parsed custom log format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogTemplate(Vec<Segment>);
+
+/// The format of the lines written to the access log.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogFormat {
+    /// The Apache Common Log Format, followed by the time taken to handle the request in
+    /// microseconds.
+    Common,
+    /// The Apache Combined Log Format, followed by the time taken to handle the request in
+    /// microseconds.
+    Combined,
+    /// One JSON object per line.
+    Json,
+    /// A template such as `$remote_addr "$request" $status`.
+    Custom(LogTemplate),
+}
+
+impl LogFormat {
+    /// Formats `entry` as a single log line, including the trailing newline.
+    pub fn format(&self, entry: &AccessLogEntry) -> String {
+        let mut line = match self {
+            Self::Common => common_log_line(entry),
+            Self::Combined => {
+                let mut line = common_log_line(entry);
+                line.push_str(" \"");
+                escape_into(entry.header("Referer").unwrap_or("-"), &mut line);
+                line.push_str("\" \"");
+                escape_into(entry.header("User-Agent").unwrap_or("-"), &mut line);
+                line.push('"');
+                line
+            }
+            Self::Json => json_log_line(entry),
+            Self::Custom(LogTemplate(segments)) => {
+                let mut line = String::new();
+
+                for segment in segments {
+                    match segment {
+                        Segment::Literal(literal) => line.push_str(literal),
+                        Segment::Variable(variable) => variable.write(entry, &mut line),
+                    }
+                }
+
+                line
+            }
+        };
+
+        if matches!(self, Self::Common | Self::Combined) {
+            let _ = write!(line, " {}", entry.latency.as_micros());
+        }
+
+        line.push('\n');
+        line
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    /// Parses one of `common`, `combined` or `json`, anything else is a custom template.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "common" => return Ok(Self::Common),
+            "combined" => return Ok(Self::Combined),
+            "json" => return Ok(Self::Json),
+            _ => {}
+        }
+
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                literal.push(c);
+                continue;
+            }
+
+            // `$$` is a literal dollar sign, `${name}` separates a variable from the text after it.
+            let braced = match chars.peek() {
+                Some('$') => {
+                    chars.next();
+                    literal.push('$');
+                    continue;
+                }
+                Some('{') => {
+                    chars.next();
+                    true
+                }
+                _ => false,
+            };
+
+            let mut name = String::new();
+
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c.to_ascii_lowercase());
+                chars.next();
+            }
+
+            if braced && chars.next() != Some('}') {
+                return Err(format!("unterminated '${{' in log format '{s}'"));
+            }
+
+            let variable = match Variable::from_name(&name) {
+                Some(variable) => variable,
+                None if name.is_empty() => {
+                    return Err(format!(
+                        "missing variable name after '$' in log format '{s}'"
+                    ))
+                }
+                None => return Err(format!("unknown log format variable '${name}'")),
+            };
+
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Variable(variable));
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self::Custom(LogTemplate(segments)))
+    }
+}
+
+/// `127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET /index.html HTTP/1.1" 200 2326`
+fn common_log_line(entry: &AccessLogEntry) -> String {
+    let mut line = String::new();
+
+    match entry.client {
+        Some(client) => {
+            let _ = write!(line, "{client}");
+        }
+        None => line.push('-'),
+    }
+
+    let _ = write!(line, " - - [{}] \"", format_clf_date(entry.time));
+    escape_into(entry.request_line().as_deref().unwrap_or("-"), &mut line);
+    let _ = write!(line, "\" {} ", entry.status);
+
+    // Empty bodies are logged as `-`, like Apache does.
+    match entry.bytes_sent {
+        0 => line.push('-'),
+        bytes_sent => {
+            let _ = write!(line, "{bytes_sent}");
+        }
+    }
+
+    line
+}
+
+fn json_log_line(entry: &AccessLogEntry) -> String {
+    let request = entry.request;
+    let request_line = entry.request_line();
+    let client = entry.client.map(|client| client.to_string());
+    let version: Option<&str> = request.map(|request| request.version.into());
+
+    let strings = [
+        ("time", Some(format_iso8601(entry.time))),
+        ("remote_addr", client),
+        ("request", request_line),
+        ("method", request.map(|request| request.method.to_string())),
+        ("uri", request.map(|request| request.path.clone())),
+        ("query", request.and_then(|request| request.query.clone())),
+        ("protocol", version.map(str::to_string)),
+    ];
+
+    let mut line = String::from("{");
+
+    for (key, value) in strings {
+        let _ = write!(line, "\"{key}\":");
+        push_json_string(value.as_deref(), &mut line);
+        line.push(',');
+    }
+
+    let _ = write!(
+        line,
+        "\"status\":{},\"body_bytes_sent\":{},\"request_time\":{:.6},",
+        entry.status,
+        entry.bytes_sent,
+        entry.latency.as_secs_f64(),
+    );
+
+    line.push_str("\"referer\":");
+    push_json_string(entry.header("Referer"), &mut line);
+    line.push_str(",\"user_agent\":");
+    push_json_string(entry.header("User-Agent"), &mut line);
+    line.push('}');
+
+    line
+}
+
+/// Appends `value` as a JSON string, or `null`.
+fn push_json_string(value: Option<&str>, line: &mut String) {
+    let Some(value) = value else {
+        line.push_str("null");
+        return;
+    };
+
+    line.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(line, "\\u{:04x}", c as u32);
+            }
+            c => line.push(c),
+        }
+    }
+
+    line.push('"');
+}
+
+/// Appends `value` with quotes, backslashes and control characters escaped the way Apache does, so
+/// that clients can't forge log lines.
+fn escape_into(value: &str, line: &mut String) {
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(line, "\\x{:02x}", c as u32);
+            }
+            c => line.push(c),
+        }
+    }
+}
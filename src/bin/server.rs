@@ -1,17 +1,59 @@
 #![deny(unsafe_code)]
 
-use vrs::configuration::*;
-use vrs::core::configuration::Configuration;
+use std::process::ExitCode;
+use vrs::cli::{self, Command};
 use vrs::core::server;
-use vrs::error::ServerError;
 
-pub fn main() -> Result<(), ServerError> {
-    let config = Configuration::read_from_vars();
+pub fn main() -> ExitCode {
+    let command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!("Try 'vrs --help' for more information.");
+            return ExitCode::from(2);
+        }
+    };
 
-    match MULTITHREADING {
-        true => server::start_multithread(config)?,
-        false => server::start_singlethread(config)?,
-    }
+    let config = match command {
+        Command::Help => {
+            println!("{}", cli::HELP);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("vrs {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Command::CheckConfig(config) => {
+            let problems = config.validate();
+            if problems.is_empty() {
+                println!("Configuration is valid.");
+                return ExitCode::SUCCESS;
+            }
+            for problem in problems {
+                eprintln!("error: {problem}");
+            }
+            return ExitCode::FAILURE;
+        }
+        Command::ReopenLogs(config) => match server::request_log_reopen(&config) {
+            Ok(()) => return ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: failed creating the trigger file: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        Command::Run(config) => config,
+    };
+
+    let result = match config.multithreading {
+        true => server::start_multithread(config),
+        false => server::start_singlethread(config),
+    };
 
-    Ok(())
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
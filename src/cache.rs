@@ -0,0 +1,298 @@
+use crate::compression::Encoding;
+use crate::file::CachedFile;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+/// Counters describing how well the file cache performs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries removed to make room for new ones.
+    pub evictions: u64,
+    /// Entries removed because the file changed on disk.
+    pub invalidations: u64,
+    pub entries: usize,
+    /// The number of bytes held by the cache.
+    pub size: usize,
+}
+
+/// The result of looking up a file in the cache.
+pub enum Lookup {
+    Hit(Arc<CachedFile>),
+    /// The file is known not to exist.
+    NotFound,
+    Miss,
+}
+
+struct CacheEntry {
+    /// `None` for files which don't exist.
+    file: Option<Arc<CachedFile>>,
+    size: usize,
+    /// When the entry was last used, the entry with the lowest tick is evicted first.
+    tick: AtomicU64,
+    /// The tick the entry is filed under in `Entries::order`. Lookups only hold a read lock, so
+    /// the order is brought up to date lazily when evicting.
+    ordered_tick: u64,
+    /// When the entry was last compared to the file on disk, in milliseconds since the cache was
+    /// created.
+    validated: AtomicU64,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, CacheEntry>,
+    /// The keys of the entries by the tick they were last known to be used at, oldest first.
+    order: BTreeMap<u64, String>,
+    size: usize,
+}
+
+/// A rough estimate of the memory every entry takes up besides its key and content: the
+/// `CacheEntry`, the slots in the map and the order, and the `String`s of the key. It's what keeps
+/// a flood of requests for distinct missing files from growing the cache far beyond its capacity.
+pub const ENTRY_OVERHEAD: usize = 128;
+
+/// The number of bytes a cached file takes up, including its compressed variants.
+fn file_size(file: &CachedFile) -> usize {
+    file.content.len() + file.encoded_size()
+}
+
+/// The number of bytes an entry of `key` with `content_size` bytes of content is charged. The key
+/// is stored twice, in the map and in the order.
+pub fn entry_size(key: &str, content_size: usize) -> usize {
+    ENTRY_OVERHEAD + 2 * key.len() + content_size
+}
+
+/// An LRU cache of files held in memory, limited to a total number of bytes. It's shared by all
+/// the worker threads.
+///
+/// Lookups only take a read lock, the recency of entries and the counters are atomics, so cached
+/// files are served concurrently. Entries are compared to the file on disk (size and modification
+/// time) once they are older than `revalidate_after`, so changed files are picked up without
+/// restarting the server. Files which don't exist are cached as well, so that repeated requests
+/// for them don't hit the disk.
+pub struct FileCache {
+    entries: RwLock<Entries>,
+    tick: AtomicU64,
+    created: Instant,
+    capacity: usize,
+    max_entry_size: usize,
+    revalidate_after: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl FileCache {
+    /// Creates a cache holding at most `capacity` bytes, with no entry larger than
+    /// `max_entry_size` bytes.
+    pub fn new(capacity: usize, max_entry_size: usize, revalidate_after: Duration) -> Self {
+        Self {
+            entries: RwLock::default(),
+            tick: AtomicU64::new(0),
+            created: Instant::now(),
+            capacity,
+            max_entry_size,
+            revalidate_after,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// Looks up the file cached as `key`, which is found at `path` on disk, and marks it as the most
+    /// recently used one. Entries for files which changed on disk are removed and reported as a
+    /// miss.
+    pub fn lookup(&self, key: &str, path: &Path) -> Lookup {
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+
+        let Some(entry) = entries.map.get(key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return Lookup::Miss;
+        };
+
+        let now = self.now();
+        let validated = entry.validated.load(Ordering::Relaxed);
+
+        if Duration::from_millis(now.saturating_sub(validated)) >= self.revalidate_after {
+            let unchanged = match (&entry.file, fs::metadata(path)) {
+                (Some(file), Ok(metadata)) => {
+                    metadata.is_file()
+                        && metadata.len() == file.content.len() as u64
+                        && metadata.modified().ok() == file.modified
+                }
+                (None, Err(_)) => true,
+                _ => false,
+            };
+
+            if !unchanged {
+                drop(entries);
+                self.entries
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(key);
+                self.invalidations.fetch_add(1, Ordering::Relaxed);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return Lookup::Miss;
+            }
+
+            entry.validated.store(now, Ordering::Relaxed);
+        }
+
+        entry.tick.store(self.next_tick(), Ordering::Relaxed);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+
+        match &entry.file {
+            Some(file) => Lookup::Hit(Arc::clone(file)),
+            None => Lookup::NotFound,
+        }
+    }
+
+    /// Caches `file` as `key`, evicting the least recently used entries if needed. Files which are
+    /// too large to be cached are handed back without being cached.
+    pub fn insert(&self, key: &str, file: CachedFile) -> Arc<CachedFile> {
+        let file = Arc::new(file);
+        let size = entry_size(key, file_size(&file));
+
+        if file_size(&file) <= self.max_entry_size && size <= self.capacity {
+            self.insert_entry(key, Some(Arc::clone(&file)), size);
+        }
+
+        file
+    }
+
+    /// Remembers that there is no file for `key`.
+    pub fn insert_not_found(&self, key: &str) {
+        let size = entry_size(key, 0);
+
+        if size <= self.capacity {
+            self.insert_entry(key, None, size);
+        }
+    }
+
+    /// Returns the content of `file`, cached as `key`, compressed with `encoding`. A newly
+    /// compressed copy is kept with the file and counts towards the size of the cache.
+    pub fn encode(&self, key: &str, file: &CachedFile, encoding: Encoding) -> Option<Arc<[u8]>> {
+        if let Some(encoded) = file.encoded(encoding) {
+            return Some(encoded);
+        }
+
+        let encoded = file.encode(encoding)?;
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+
+        let file_size = match entries.map.get(key).and_then(|entry| entry.file.as_ref()) {
+            Some(file) => file_size(file),
+            None => return Some(encoded),
+        };
+
+        let size = entry_size(key, file_size);
+
+        if file_size > self.max_entry_size || size > self.capacity {
+            entries.remove(key);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(entry) = entries.take(key) {
+            // Make room without evicting the entry itself, it was just used.
+            self.evict_until_fits(&mut entries, size);
+            entries.insert(key, CacheEntry { size, ..entry });
+        }
+
+        Some(encoded)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: entries.map.len(),
+            size: entries.size,
+        }
+    }
+
+    /// Inserts an entry of `size` bytes, replacing the previous entry of `key`.
+    fn insert_entry(&self, key: &str, file: Option<Arc<CachedFile>>, size: usize) {
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+
+        entries.remove(key);
+        self.evict_until_fits(&mut entries, size);
+
+        let tick = self.next_tick();
+
+        entries.insert(
+            key,
+            CacheEntry {
+                file,
+                size,
+                tick: AtomicU64::new(tick),
+                ordered_tick: tick,
+                validated: AtomicU64::new(self.now()),
+            },
+        );
+    }
+
+    /// Evicts the least recently used entries until `size` more bytes fit.
+    ///
+    /// Entries which were used since they were filed in the order are filed again under their
+    /// current tick instead, which happens at most once per entry since the ticks can't change
+    /// while the write lock is held.
+    fn evict_until_fits(&self, entries: &mut Entries, size: usize) {
+        while entries.size + size > self.capacity {
+            let Some((ordered_tick, key)) = entries.order.pop_first() else {
+                break;
+            };
+
+            let Some(entry) = entries.map.get_mut(&key) else {
+                continue;
+            };
+
+            let tick = entry.tick.load(Ordering::Relaxed);
+
+            if tick != ordered_tick {
+                entry.ordered_tick = tick;
+                entries.order.insert(tick, key);
+                continue;
+            }
+
+            entries.remove(&key);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Milliseconds since the cache was created.
+    fn now(&self) -> u64 {
+        self.created.elapsed().as_millis() as u64
+    }
+}
+
+impl Entries {
+    fn insert(&mut self, key: &str, entry: CacheEntry) {
+        self.size += entry.size;
+        self.order.insert(entry.ordered_tick, key.to_string());
+        self.map.insert(key.to_string(), entry);
+    }
+
+    /// Removes the entry of `key` and hands it back.
+    fn take(&mut self, key: &str) -> Option<CacheEntry> {
+        let entry = self.map.remove(key)?;
+        self.size -= entry.size;
+        self.order.remove(&entry.ordered_tick);
+        Some(entry)
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.take(key);
+    }
+}
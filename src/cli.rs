@@ -0,0 +1,153 @@
+use crate::configuration::CONFIG_FILE_PATH;
+use crate::core::config_file::ConfigError;
+use crate::core::configuration::Configuration;
+use std::fmt;
+use std::path::Path;
+
+pub const HELP: &str = "\
+Usage: vrs [OPTIONS]
+
+Options:
+    --config <FILE>         Read the runtime configuration from FILE (default: /etc/vrs/vrs.conf)
+    --addr <ADDR>           Address to listen on
+    --port <PORT>           Port to listen on
+    --root <DIR>            Directory containing the static files to serve
+    --threads <N>           Number of worker threads, more than 1 enables multithreaded mode
+    --no-cache              Disable file caching
+    --listing <MODE>        Directory listing: html, json or off
+    --check-config          Validate the configuration and the fallback pages, then exit
+    --reopen-logs           Make the running server reopen its log file, then exit
+    -h, --help              Print this help and exit
+    -V, --version           Print the version and exit
+
+Options given on the command line take precedence over the configuration file.";
+
+/// What the binary should do after parsing the command-line arguments.
+pub enum Command {
+    Run(Configuration),
+    CheckConfig(Configuration),
+    ReopenLogs(Configuration),
+    Help,
+    Version,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Config(ConfigError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) => write!(f, "{message}"),
+            Self::Config(e) => write!(f, "invalid configuration file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<ConfigError> for CliError {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
+/// Parses the command-line arguments, without the program name.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, CliError> {
+    let mut options: Vec<(String, Option<String>)> = vec![];
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+
+        let takes_value = matches!(
+            flag.as_str(),
+            "--config" | "--addr" | "--port" | "--root" | "--threads" | "--listing"
+        );
+
+        let value = match (takes_value, inline_value) {
+            (true, Some(value)) => Some(value),
+            (true, None) => match args.next() {
+                Some(value) => Some(value),
+                None => return Err(CliError::Usage(format!("'{flag}' requires a value"))),
+            },
+            (false, Some(_)) => {
+                return Err(CliError::Usage(format!("'{flag}' doesn't take a value")))
+            }
+            (false, None) => None,
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--config" | "--addr" | "--port" | "--root" | "--threads" | "--listing"
+            | "--no-cache" | "--check-config" | "--reopen-logs" => options.push((flag, value)),
+            _ => return Err(CliError::Usage(format!("unknown option '{flag}'"))),
+        }
+    }
+
+    let mut config = match options.iter().rev().find(|(flag, _)| flag == "--config") {
+        Some((_, Some(path))) => Configuration::read_from_file(path)?,
+        _ if Path::new(CONFIG_FILE_PATH).exists() => {
+            Configuration::read_from_file(CONFIG_FILE_PATH)?
+        }
+        _ => Configuration::read_from_vars(),
+    };
+
+    let mut check_config = false;
+    let mut reopen_logs = false;
+
+    for (flag, value) in options {
+        let value = value.unwrap_or_default();
+
+        let result = match flag.as_str() {
+            "--addr" => config.set("addr", &value),
+            "--port" => config.set("port", &value),
+            "--root" => config.set("absolute_static_content_path", &value),
+            "--threads" => config.set("num_of_threads", &value).map(|_| {
+                config.multithreading = config.num_of_threads > 1;
+            }),
+            "--no-cache" => config.set("cache_files", "false"),
+            "--listing" => match value.as_str() {
+                "html" | "json" => {
+                    config.allow_directory_listing = true;
+                    config.format_directory_listing_as_json = value == "json";
+                    Ok(())
+                }
+                "off" => {
+                    config.allow_directory_listing = false;
+                    Ok(())
+                }
+                _ => Err(format!("expected 'html', 'json' or 'off', found '{value}'")),
+            },
+            "--check-config" => {
+                check_config = true;
+                Ok(())
+            }
+            "--reopen-logs" => {
+                reopen_logs = true;
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(message) = result {
+            return Err(CliError::Usage(format!("{flag}: {message}")));
+        }
+    }
+
+    if check_config {
+        Ok(Command::CheckConfig(config))
+    } else if reopen_logs {
+        Ok(Command::ReopenLogs(config))
+    } else {
+        Ok(Command::Run(config))
+    }
+}
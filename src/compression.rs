@@ -0,0 +1,649 @@
+//! A dependency free DEFLATE (RFC 1951) encoder and the gzip (RFC 1952) and zlib (RFC 1950)
+//! wrappers used for the `gzip` and `deflate` content codings.
+//!
+//! The encoder uses LZ77 with hash chains and lazy matching, followed by dynamic Huffman codes
+//! computed for every block. It favors simplicity over squeezing out the last few percent.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A content coding which the server can send (RFC 9110 §8.4.1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    /// The encodings the server can produce on the fly, in order of preference.
+    pub const ALL: [Encoding; 2] = [Encoding::Gzip, Encoding::Deflate];
+
+    /// The encodings which are served from precompressed files next to the original file, in
+    /// order of preference.
+    pub const PRECOMPRESSED: [Encoding; 2] = [Encoding::Brotli, Encoding::Gzip];
+
+    /// The name of the coding as used in `Accept-Encoding` and `Content-Encoding`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// The extension appended to the name of a file precompressed with this coding.
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gz",
+            Encoding::Deflate => "zz",
+        }
+    }
+
+    /// Compresses `data`, or returns `None` if the coding can only be served from precompressed
+    /// files.
+    pub fn encode(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Brotli => None,
+            Encoding::Gzip => Some(gzip(data)),
+            Encoding::Deflate => Some(zlib(data)),
+        }
+    }
+}
+
+/// Picks the preferred encoding out of `available` which the client accepts according to its
+/// `Accept-Encoding` header. Codings with equal q-values are ordered by their position in
+/// `available`.
+pub fn negotiate(accept_encoding: &str, available: &[Encoding]) -> Option<Encoding> {
+    let mut wildcard = None;
+    let mut qvalues: Vec<(&str, u16)> = vec![];
+
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+        let coding = params.next().unwrap_or_default().trim();
+
+        if coding.is_empty() {
+            continue;
+        }
+
+        let mut qvalue = 1000;
+
+        for param in params {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("q") {
+                    qvalue = parse_qvalue(value.trim()).unwrap_or(0);
+                }
+            }
+        }
+
+        if coding == "*" {
+            wildcard = Some(qvalue);
+        } else {
+            qvalues.push((coding, qvalue));
+        }
+    }
+
+    let mut best: Option<(Encoding, u16)> = None;
+
+    for encoding in available {
+        let qvalue = qvalues
+            .iter()
+            .find(|(coding, _)| {
+                coding.eq_ignore_ascii_case(encoding.as_str())
+                    || (*encoding == Encoding::Gzip && coding.eq_ignore_ascii_case("x-gzip"))
+            })
+            .map(|(_, qvalue)| *qvalue)
+            .or(wildcard)
+            .unwrap_or(0);
+
+        if qvalue > 0 && best.is_none_or(|(_, best)| qvalue > best) {
+            best = Some((*encoding, qvalue));
+        }
+    }
+
+    best.map(|(encoding, _)| encoding)
+}
+
+/// Parses a q-value (RFC 9110 §12.4.2) into thousandths.
+fn parse_qvalue(value: &str) -> Option<u16> {
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let frac = format!("{frac:0<3}").parse::<u16>().ok()?;
+
+    match int {
+        "0" => Some(frac),
+        "1" if frac == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+/// Returns whether responses of `mime_type` should be compressed. Entries in `allowed` are either
+/// exact types (`application/json`) or wildcards of a top-level type (`text/*`), parameters such
+/// as the charset are ignored.
+pub fn is_compressible(allowed: &[String], mime_type: &str) -> bool {
+    let mime_type = mime_type.split(';').next().unwrap_or_default().trim();
+
+    allowed
+        .iter()
+        .any(|allowed| match allowed.strip_suffix("/*") {
+            Some(top_level) => mime_type
+                .split_once('/')
+                .is_some_and(|(mime_top_level, _)| mime_top_level.eq_ignore_ascii_case(top_level)),
+            None => allowed.eq_ignore_ascii_case(mime_type),
+        })
+}
+
+/// Compresses `data` into the gzip file format.
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    // Magic number, CM = deflate, no flags, no modification time, no extra flags, unknown OS.
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    out.extend(deflate(data));
+    out.extend(crc32(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Compresses `data` into the zlib format, which is what the `deflate` content coding refers to.
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    // CM = deflate with a 32K window, default compression level, no dictionary.
+    let mut out = vec![0x78, 0x9c];
+    out.extend(deflate(data));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, b| {
+        CRC32_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    // 5552 is the largest number of bytes which can be summed before `b` could overflow.
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+const WINDOW_SIZE: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: usize = 15;
+/// How many earlier positions with the same hash are compared before settling for a match.
+const MAX_CHAIN: usize = 128;
+/// Matches at least this long are taken right away instead of checking the next position.
+const LAZY_MATCH: usize = 32;
+/// The maximum number of symbols in a block, each block gets its own Huffman codes.
+const BLOCK_SIZE: usize = 1 << 14;
+const NONE: u32 = u32::MAX;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order in which the code length code lengths are stored in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+#[derive(Clone, Copy)]
+enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+/// Compresses `data` into a raw DEFLATE stream.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let tokens = lz77(data);
+    let mut writer = BitWriter::default();
+    let mut blocks = tokens.chunks(BLOCK_SIZE).peekable();
+
+    if blocks.peek().is_none() {
+        write_block(&mut writer, &[], true);
+    }
+
+    while let Some(block) = blocks.next() {
+        write_block(&mut writer, block, blocks.peek().is_none());
+    }
+
+    writer.finish()
+}
+
+fn lz77(data: &[u8]) -> Vec<Token> {
+    let mut matcher = Matcher {
+        data,
+        head: vec![NONE; 1 << HASH_BITS],
+        prev: vec![NONE; WINDOW_SIZE],
+        inserted: 0,
+    };
+    let mut tokens = Vec::with_capacity(data.len() / 2);
+    // A match found at the previous position which hasn't been emitted yet, in case the match at
+    // the current position turns out to be longer.
+    let mut pending: Option<(usize, usize)> = None;
+    let mut i = 0;
+
+    while i < data.len() {
+        let (length, distance) = matcher.longest_match(i);
+
+        match pending {
+            Some((pending_length, pending_distance)) if length <= pending_length => {
+                tokens.push(Token::Match {
+                    length: pending_length as u16,
+                    distance: pending_distance as u16,
+                });
+                i += pending_length - 1;
+                pending = None;
+                continue;
+            }
+            Some(_) => tokens.push(Token::Literal(data[i - 1])),
+            None => {}
+        }
+
+        if length >= LAZY_MATCH {
+            tokens.push(Token::Match {
+                length: length as u16,
+                distance: distance as u16,
+            });
+            i += length;
+            pending = None;
+        } else if length >= MIN_MATCH {
+            pending = Some((length, distance));
+            i += 1;
+        } else {
+            tokens.push(Token::Literal(data[i]));
+            pending = None;
+            i += 1;
+        }
+    }
+
+    if let Some((length, distance)) = pending {
+        tokens.push(Token::Match {
+            length: length as u16,
+            distance: distance as u16,
+        });
+    }
+
+    tokens
+}
+
+struct Matcher<'a> {
+    data: &'a [u8],
+    /// The most recent position of every hash.
+    head: Vec<u32>,
+    /// The previous position with the same hash for every position inside the window.
+    prev: Vec<u32>,
+    /// Every position before this one has been inserted into the hash chains.
+    inserted: usize,
+}
+
+impl Matcher<'_> {
+    fn hash(&self, pos: usize) -> usize {
+        let bytes = &self.data[pos..pos + MIN_MATCH];
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    }
+
+    /// Finds the longest earlier occurrence of the bytes at `pos`, returning its length and
+    /// distance or a length of 0 if there is none.
+    fn longest_match(&mut self, pos: usize) -> (usize, usize) {
+        while self.inserted < pos {
+            if self.inserted + MIN_MATCH <= self.data.len() {
+                let hash = self.hash(self.inserted);
+                self.prev[self.inserted % WINDOW_SIZE] = self.head[hash];
+                self.head[hash] = self.inserted as u32;
+            }
+            self.inserted += 1;
+        }
+
+        let max_length = MAX_MATCH.min(self.data.len() - pos);
+
+        if max_length < MIN_MATCH {
+            return (0, 0);
+        }
+
+        let (mut best_length, mut best_distance) = (0, 0);
+        let mut candidate = self.head[self.hash(pos)];
+        let mut chain = 0;
+
+        while candidate != NONE && chain < MAX_CHAIN {
+            let start = candidate as usize;
+
+            if pos - start > WINDOW_SIZE {
+                break;
+            }
+
+            let length = self.data[start..start + max_length]
+                .iter()
+                .zip(&self.data[pos..pos + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if length > best_length {
+                best_length = length;
+                best_distance = pos - start;
+
+                if length == max_length {
+                    break;
+                }
+            }
+
+            let next = self.prev[start % WINDOW_SIZE];
+
+            // Older positions are always smaller, anything else was overwritten in the window.
+            if next == NONE || next >= candidate {
+                break;
+            }
+
+            candidate = next;
+            chain += 1;
+        }
+
+        if best_length >= MIN_MATCH {
+            (best_length, best_distance)
+        } else {
+            (0, 0)
+        }
+    }
+}
+
+fn length_code(length: u16) -> usize {
+    LENGTH_BASE.partition_point(|base| *base <= length) - 1
+}
+
+fn distance_code(distance: u16) -> usize {
+    DISTANCE_BASE.partition_point(|base| *base <= distance) - 1
+}
+
+/// Writes the tokens as a single block compressed with dynamic Huffman codes.
+fn write_block(writer: &mut BitWriter, tokens: &[Token], last: bool) {
+    let mut literal_freqs = [0u32; 286];
+    let mut distance_freqs = [0u32; 30];
+
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literal_freqs[byte as usize] += 1,
+            Token::Match { length, distance } => {
+                literal_freqs[257 + length_code(length)] += 1;
+                distance_freqs[distance_code(distance)] += 1;
+            }
+        }
+    }
+
+    // End of block
+    literal_freqs[256] += 1;
+
+    let literal_lengths = huffman_lengths(&literal_freqs, 15);
+    let distance_lengths = huffman_lengths(&distance_freqs, 15);
+    let literal_codes = huffman_codes(&literal_lengths);
+    let distance_codes = huffman_codes(&distance_lengths);
+
+    let num_literals = 257.max(literal_lengths.iter().rposition(|l| *l > 0).unwrap_or(0) + 1);
+    let num_distances = 1.max(distance_lengths.iter().rposition(|l| *l > 0).unwrap_or(0) + 1);
+
+    let mut lengths = literal_lengths[..num_literals].to_vec();
+    lengths.extend(&distance_lengths[..num_distances]);
+
+    // Run-length encode the code lengths as (symbol, extra bits value).
+    let mut code_length_symbols: Vec<(u8, u8)> = vec![];
+    let mut i = 0;
+
+    while i < lengths.len() {
+        let length = lengths[i];
+        let run = lengths[i..].iter().take_while(|l| **l == length).count();
+
+        if length == 0 && run >= 11 {
+            let run = run.min(138);
+            code_length_symbols.push((18, (run - 11) as u8));
+            i += run;
+        } else if length == 0 && run >= 3 {
+            code_length_symbols.push((17, (run - 3) as u8));
+            i += run;
+        } else if length != 0 && run >= 4 {
+            // The first length is sent as is, the rest are repeats of it.
+            let run = (run - 1).min(6);
+            code_length_symbols.push((length, 0));
+            code_length_symbols.push((16, (run - 3) as u8));
+            i += run + 1;
+        } else {
+            code_length_symbols.push((length, 0));
+            i += 1;
+        }
+    }
+
+    let mut code_length_freqs = [0u32; 19];
+    for (symbol, _) in &code_length_symbols {
+        code_length_freqs[*symbol as usize] += 1;
+    }
+
+    let code_length_lengths = huffman_lengths(&code_length_freqs, 7);
+    let code_length_codes = huffman_codes(&code_length_lengths);
+
+    let num_code_lengths = 4.max(
+        CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|symbol| code_length_lengths[*symbol] > 0)
+            .unwrap_or(0)
+            + 1,
+    );
+
+    writer.write_bits(last as u32, 1);
+    writer.write_bits(2, 2);
+    writer.write_bits((num_literals - 257) as u32, 5);
+    writer.write_bits((num_distances - 1) as u32, 5);
+    writer.write_bits((num_code_lengths - 4) as u32, 4);
+
+    for symbol in &CODE_LENGTH_ORDER[..num_code_lengths] {
+        writer.write_bits(code_length_lengths[*symbol] as u32, 3);
+    }
+
+    for (symbol, extra) in code_length_symbols {
+        let symbol = symbol as usize;
+        writer.write_bits(code_length_codes[symbol], code_length_lengths[symbol]);
+
+        match symbol {
+            16 => writer.write_bits(extra as u32, 2),
+            17 => writer.write_bits(extra as u32, 3),
+            18 => writer.write_bits(extra as u32, 7),
+            _ => {}
+        }
+    }
+
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => {
+                let byte = byte as usize;
+                writer.write_bits(literal_codes[byte], literal_lengths[byte]);
+            }
+            Token::Match { length, distance } => {
+                let code = length_code(length);
+                writer.write_bits(literal_codes[257 + code], literal_lengths[257 + code]);
+                writer.write_bits((length - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code]);
+
+                let code = distance_code(distance);
+                writer.write_bits(distance_codes[code], distance_lengths[code]);
+                writer.write_bits(
+                    (distance - DISTANCE_BASE[code]) as u32,
+                    DISTANCE_EXTRA[code],
+                );
+            }
+        }
+    }
+
+    writer.write_bits(literal_codes[256], literal_lengths[256]);
+}
+
+/// Computes Huffman code lengths no longer than `limit` bits for the given symbol frequencies.
+fn huffman_lengths(freqs: &[u32], limit: u8) -> Vec<u8> {
+    let mut freqs = freqs.to_vec();
+
+    // Decoders expect at least two codes, so that every code is at least one bit long.
+    for symbol in 0..2 {
+        if freqs.iter().filter(|f| **f > 0).count() < 2 && freqs[symbol] == 0 {
+            freqs[symbol] = 1;
+        }
+    }
+
+    loop {
+        let lengths = huffman_tree_depths(&freqs);
+
+        if lengths.iter().all(|l| *l <= limit) {
+            return lengths;
+        }
+
+        // Flatten the distribution until the tree is shallow enough.
+        for freq in freqs.iter_mut().filter(|f| **f > 0) {
+            *freq = (*freq >> 1).max(1);
+        }
+    }
+}
+
+fn huffman_tree_depths(freqs: &[u32]) -> Vec<u8> {
+    let mut heap = BinaryHeap::new();
+    // Parent of every node, leaves are the symbols and inner nodes are appended after them.
+    let mut parents: Vec<usize> = vec![usize::MAX; freqs.len()];
+
+    for (symbol, freq) in freqs.iter().enumerate() {
+        if *freq > 0 {
+            heap.push(Reverse((*freq as u64, symbol)));
+        }
+    }
+
+    while heap.len() > 1 {
+        let Reverse((freq_a, a)) = heap.pop().unwrap_or_default();
+        let Reverse((freq_b, b)) = heap.pop().unwrap_or_default();
+        let node = parents.len();
+        parents.push(usize::MAX);
+        parents[a] = node;
+        parents[b] = node;
+        heap.push(Reverse((freq_a + freq_b, node)));
+    }
+
+    (0..freqs.len())
+        .map(|symbol| {
+            if freqs[symbol] == 0 {
+                return 0;
+            }
+
+            let mut depth = 0;
+            let mut node = symbol;
+
+            while parents[node] != usize::MAX {
+                node = parents[node];
+                depth += 1;
+            }
+
+            depth.min(u8::MAX as usize) as u8
+        })
+        .collect()
+}
+
+/// Assigns canonical Huffman codes to the code lengths (RFC 1951 §3.2.2), bit reversed since
+/// DEFLATE writes Huffman codes starting with the most significant bit.
+fn huffman_codes(lengths: &[u8]) -> Vec<u32> {
+    let max_length = lengths.iter().copied().max().unwrap_or(0) as usize;
+    let mut length_count = vec![0u32; max_length + 1];
+
+    for length in lengths.iter().filter(|l| **l > 0) {
+        length_count[*length as usize] += 1;
+    }
+
+    let mut next_code = vec![0u32; max_length + 1];
+    let mut code = 0;
+
+    for bits in 1..=max_length {
+        code = (code + length_count[bits - 1]) << 1;
+        next_code[bits] = code;
+    }
+
+    lengths
+        .iter()
+        .map(|length| {
+            if *length == 0 {
+                return 0;
+            }
+
+            let code = next_code[*length as usize];
+            next_code[*length as usize] += 1;
+            code.reverse_bits() >> (32 - *length as u32)
+        })
+        .collect()
+}
+
+/// Packs values into bytes starting at the least significant bit, as required by DEFLATE.
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, bits: u8) {
+        self.buffer |= (value as u64) << self.bits;
+        self.bits += bits;
+
+        while self.bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
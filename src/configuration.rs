@@ -2,26 +2,56 @@
 
 Read the documentation at /docs/configuration.md if you want to find out how any of these configuration variables work.
 
+The values below are only the defaults. Every one of them can be overridden at runtime in the configuration file found at CONFIG_FILE_PATH, using the lowercase name of the constant as the key (e.g. "port = 8080").
+
 CAUTION: PATHS MUST NOT CONTAIN TRAILING SLASH
 
 */
 
-use crate::http::HttpProtocolVersion;
+use crate::access_log::LogFormat;
+use crate::http::{HttpProtocolVersion, Method};
 
 // Start of general server configuration 
 
+// The path of the runtime configuration file. If the file doesn't exist the server starts with the defaults found in this file.
+pub const CONFIG_FILE_PATH: &str = "/etc/vrs/vrs.conf";
+
 // You should provide this variable the absolute path (/absolute/path/to/static) which should contain all the static files you went the web server to serve. The web server will take care of serving the files, all you need to know is that you need to put the same path inside the $STATIC variable in setup.sh.
 pub const ABSOLUTE_STATIC_CONTENT_PATH: &str = "/var/www/static";
 
-// The absolute path to which the server request logs should be saved (/absolute/path/to/logs). Note that this setting can be ignored if MULTITHREADING is enabled because the server doesn't save logs outside of singlethreaded mode.
+// The absolute path to which the server request logs should be saved (/absolute/path/to/logs). Requests are logged to access.log inside this directory, rotated files are kept next to it as access.log.<unix timestamp>.
 pub const ABSOLUTE_LOGS_PATH: &str = "/var/www/logs";
 
-// Boolean for specifying whether you want the server to save request logs for each request or not.
+// Boolean for specifying whether you want the server to save request logs for each request or not. Logs are written by a dedicated thread in both singlethreaded and multithreaded mode, so serving requests never waits on the disk.
 pub const SAVE_LOGS: bool = true;
 
-// If enabled, the web server will cache previously requested files from the clients by storing them into the computer's free memory and displaying them instead of doing a filesystem operation on every request which is costly. This can double the performance on extremely large files, but in most cases it shouldn't make that much of a difference. If enabled, once a specific file has been requested its contents will never be updated after being changed. File caching can use up much memory depending on the files' sizes.
+// The format of the request log lines: LogFormat::Common and LogFormat::Combined for the Apache formats (followed by the time taken to handle the request in microseconds), LogFormat::Json for one JSON object per line. A custom template such as `$remote_addr [$time_local] "$request" $status $body_bytes_sent $request_time` can be set in the runtime configuration file, see /docs/configuration.md for the available variables.
+pub const LOG_FORMAT: LogFormat = LogFormat::Combined;
+
+// The size in bytes after which the log file is rotated. Set to 0 to disable rotation by size.
+pub const LOG_MAX_SIZE: u64 = 100 * 1024 * 1024;
+
+// Boolean for specifying whether the log file should be rotated at the start of every day (UTC).
+pub const LOG_ROTATE_DAILY: bool = true;
+
+// The number of rotated log files to keep, the oldest ones are removed. Set to 0 to keep all of them.
+pub const LOG_MAX_FILES: usize = 14;
+
+// Boolean for specifying whether rotated log files should be compressed with gzip.
+pub const LOG_COMPRESS: bool = true;
+
+// If enabled, the web server will cache previously requested files from the clients by storing them into the computer's free memory and displaying them instead of doing a filesystem operation on every request which is costly. This can double the performance on extremely large files, but in most cases it shouldn't make that much of a difference. Requests for files which don't exist are cached as well.
 pub const CACHE_FILES: bool = true;
 
+// The maximum number of bytes the file cache may use (including compressed copies of the files). The least recently used files are removed from the cache once it's full.
+pub const CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+// The number of seconds after which a cached file is compared to the file on disk (size and modification time) the next time it's requested, so that changed files are served without restarting the server. Set to 0 to check on every request.
+pub const CACHE_REVALIDATE_AFTER: u64 = 2;
+
+// Files larger than this number of bytes are streamed from the disk in chunks instead of being read into memory, which also means that they are never cached nor compressed on the fly.
+pub const CACHE_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
 // The IPv4 address the server should use (e.g. 0.0.0.0 or 127.0.0.1). It is recommended to change this to a loopback address like 127.0.0.1 during development.
 // Production note: should be "0.0.0.0" when running inside a Docker container. 
 pub const ADDR: &str = "0.0.0.0";
@@ -31,18 +61,38 @@ pub const PORT: u32 = 80;
 // Boolean which should be set to false if you wish to not print license information for the software upon starting. Note that keeping it enabled is the least you can do to show respect to the contributors who spent their time on improving this project.
 pub const PRINT_LICENSE_INFO_AT_START: bool = true;
 
+// The files which are served when a directory is requested, the first one found is used. Requests for a directory without a trailing slash are redirected to the path with the slash (/docs to /docs/) so that relative links work.
+pub const INDEX_FILES: [&str; 2] = ["index.html", "index.htm"];
+
 // Boolean to specify whether you want the server to allow listing the contents of a directory whenever a user tries to access a directory and not a particular file.
 pub const ALLOW_DIRECTORY_LISTING: bool = true;
 
 // Boolean to specify whether the returned directory's contents should be JSON. If it is set to false, it will return HTML. Can be ignored if ALLOW_DIRECTORY_LISTING is set to false.
 pub const FORMAT_DIRECTORY_LISTING_AS_JSON: bool = false;
 
+// Boolean to specify whether paths without an extension should be served from the HTML file of the same name when they don't exist themselves (/about from about.html), as produced by most static site generators.
+pub const CLEAN_URLS: bool = false;
+
+// Boolean to specify whether requests for HTML files should be redirected to their clean URL (/about.html to /about and /docs/index.html to /docs/) with a 301 so that every page has a single URL. Can be ignored if CLEAN_URLS is set to false.
+pub const CLEAN_URLS_REDIRECT: bool = false;
+
+// Boolean to specify whether requests for paths which don't exist should be answered with SPA_FALLBACK_FILE and a 200 instead of the 404 fallback page, so that the client-side router of a single-page application (React, Vue, ...) can handle them.
+pub const SPA_FALLBACK: bool = false;
+
+// The file, relative to ABSOLUTE_STATIC_CONTENT_PATH, which is served for unknown paths when SPA_FALLBACK is enabled.
+pub const SPA_FALLBACK_FILE: &str = "index.html";
+
+// The SPA fallback only applies to paths starting with one of these prefixes. Leave it empty to apply it to every path.
+pub const SPA_INCLUDE_PREFIXES: [&str; 1] = ["/"];
+
+// Paths starting with one of these prefixes always get a 404 when they don't exist, even if they're included above (e.g. "/api/" or "/assets/").
+pub const SPA_EXCLUDE_PREFIXES: [&str; 0] = [];
+
 // End of general server configuration 
 
 // Start of multithreading configuration 
 
-// Boolean used to specify whether you want the server to use multiple threads (workers) or not. Logs aren't supported when using this functionality.
-// Production note: Multithreaded mode currently doesn't support logs. 
+// Boolean used to specify whether you want the server to use multiple threads (workers) or not.
 pub const MULTITHREADING: bool = false;
 pub const NUM_OF_THREADS: usize = 1;
 
@@ -51,10 +101,54 @@ pub const NUM_OF_THREADS: usize = 1;
 // Enum for specifying whether you want to use HTTP/1.1 or HTTP/2 protocol version.
 pub const HTTP_PROTOCOL_VERSION: HttpProtocolVersion = HttpProtocolVersion::OneDotOne;
 
+// Start of persistent connections configuration 
+
+// Boolean for specifying whether connections should be kept open after a response so that clients can send further requests without a new TCP handshake (HTTP/1.1 keep-alive). Clients can still ask for the connection to be closed with the "Connection: close" header, HTTP/1.0 clients have to ask for it with "Connection: keep-alive".
+// Keep-alive is only used in multithreaded mode, a single thread waiting on an idle connection would block every other client until the connection times out.
+// Production note: every idle connection occupies a worker until it's closed or times out, so more idle connections than NUM_OF_THREADS (browsers open several per page) block other clients for up to KEEP_ALIVE_TIMEOUT. Use enough threads and keep KEEP_ALIVE_TIMEOUT low.
+pub const KEEP_ALIVE: bool = true;
+
+// The number of seconds a persistent connection may stay idle before the server closes it.
+pub const KEEP_ALIVE_TIMEOUT: u64 = 5;
+
+// The maximum number of requests served on a single connection before the server closes it.
+pub const KEEP_ALIVE_MAX_REQUESTS: usize = 100;
+
+// End of persistent connections configuration 
+
+// Start of compression configuration 
+
+// Boolean used to specify whether responses should be compressed with gzip or deflate when the client supports it ("Accept-Encoding"). Compressed files are kept in the file cache so that they are only compressed once when CACHE_FILES is enabled.
+pub const COMPRESSION: bool = true;
+
+// Files smaller than this number of bytes are never compressed since the savings wouldn't make up for the extra work.
+pub const COMPRESSION_MIN_SIZE: usize = 1024;
+
+// The MIME types which should be compressed. Either an exact type or a wildcard such as "text/*". Formats which are already compressed (images, videos, archives, ...) barely shrink and shouldn't be listed.
+pub const COMPRESSION_MIME_TYPES: [&str; 12] = [
+    "text/*",
+    "application/javascript",
+    "application/json",
+    "application/ld+json",
+    "application/manifest+json",
+    "application/xml",
+    "application/xhtml+xml",
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/wasm",
+    "image/svg+xml",
+    "image/x-icon",
+];
+
+// Boolean used to specify whether precompressed files next to the requested file (e.g. "app.js.br" and "app.js.gz" for "app.js") should be sent instead of the requested file when the client supports their encoding. They take priority over compressing the file on the fly.
+pub const SERVE_PRECOMPRESSED: bool = true;
+
+// End of compression configuration 
+
 // Start of CORS configuration 
 
-// Used to specify which HTTP methods you wish to allow. Must contain atleast one. Most of the time you only need the GET method to serve plain HTML.
-pub const ALLOWED_METHODS: [&str; 1] = ["GET"];
+// Used to specify which HTTP methods you wish to allow. Must contain atleast one. Most of the time you only need the GET method to serve plain HTML, HEAD lets clients fetch the headers of a response (e.g. its size) without downloading the body.
+pub const ALLOWED_METHODS: [Method; 2] = [Method::Get, Method::Head];
 
 
 // Boolean used to specify whether you want to allow external web servers (outside your local network) to fetch data from VRS. There shouldn't be any problem with keeping this on.
@@ -64,6 +158,18 @@ pub const ALLOW_ALL_ORIGINS: bool = true;
 // If you don't want to allow all origins as explained above, you may also just allow a few specific origins to scrap from the server. If you want to block all origins, set ALLOW_ALL_ORIGINS to false and leave the ALLOWED_ORIGINS slice empty.
 pub const ALLOWED_ORIGINS: [&str; 0] = [];
 
+// Request headers which cross-origin requests may use, sent in the "Access-Control-Allow-Headers" header when answering CORS preflight (OPTIONS) requests. Leave it empty to only allow the headers browsers consider safe.
+pub const CORS_ALLOWED_HEADERS: [&str; 0] = [];
+
+// Response headers which scripts from other origins may read, sent in the "Access-Control-Expose-Headers" header. Leave it empty to only expose the headers browsers consider safe.
+pub const CORS_EXPOSE_HEADERS: [&str; 0] = [];
+
+// The number of seconds browsers may cache the answer to a CORS preflight request ("Access-Control-Max-Age"). Set to 0 to leave it to the browser's default.
+pub const CORS_MAX_AGE: u64 = 86400;
+
+// Boolean used to specify whether cross-origin requests may include credentials such as cookies ("Access-Control-Allow-Credentials"). When enabled together with ALLOW_ALL_ORIGINS the request's origin is echoed back instead of "*" because browsers refuse the wildcard for credentialed requests.
+pub const CORS_ALLOW_CREDENTIALS: bool = false;
+
 // End of CORS configuration 
 
 // Start of headers configuration 
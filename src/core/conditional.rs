@@ -0,0 +1,88 @@
+use crate::http::Method;
+use crate::request::Request;
+use crate::status::StatusCode;
+use crate::time::{parse_http_date, truncate_to_secs};
+use std::time::SystemTime;
+
+/// Compares two entity tags, ignoring the weak indicator when `weak` is set.
+fn etag_eq(a: &str, b: &str, weak: bool) -> bool {
+    if weak {
+        a.trim_start_matches("W/") == b.trim_start_matches("W/")
+    } else {
+        !a.starts_with("W/") && !b.starts_with("W/") && a == b
+    }
+}
+
+/// Whether the list of entity tags in a header (`If-Match`, `If-None-Match`) matches `etag`.
+fn etag_list_matches(request: &Request, header: &str, etag: &str, weak: bool) -> bool {
+    request
+        .headers
+        .get_list(header)
+        .any(|tag| tag == "*" || etag_eq(tag, etag, weak))
+}
+
+/// Evaluates the preconditions of a request for a file in the order given by RFC 9110 section
+/// 13.2.2.
+///
+/// Returns the status code the server should respond with instead of the file, if any.
+pub fn evaluate_preconditions(
+    request: &Request,
+    etag: &str,
+    modified: Option<SystemTime>,
+) -> Option<StatusCode> {
+    let modified = modified.map(truncate_to_secs);
+
+    if request.headers.contains("If-Match") {
+        if !etag_list_matches(request, "If-Match", etag, false) {
+            return Some(StatusCode::PreconditionFailed);
+        }
+    } else if let Some(date) = request
+        .headers
+        .get("If-Unmodified-Since")
+        .and_then(parse_http_date)
+    {
+        if modified.is_some_and(|modified| modified > date) {
+            return Some(StatusCode::PreconditionFailed);
+        }
+    }
+
+    let is_get_or_head = request.method == Method::Get || request.method == Method::Head;
+
+    if request.headers.contains("If-None-Match") {
+        if etag_list_matches(request, "If-None-Match", etag, true) {
+            return Some(if is_get_or_head {
+                StatusCode::NotModified
+            } else {
+                StatusCode::PreconditionFailed
+            });
+        }
+    } else if let Some(date) = request
+        .headers
+        .get("If-Modified-Since")
+        .and_then(parse_http_date)
+    {
+        if is_get_or_head && modified.is_some_and(|modified| modified <= date) {
+            return Some(StatusCode::NotModified);
+        }
+    }
+
+    None
+}
+
+/// Evaluates the `If-Range` header: the range may only be served if the file is still the one the
+/// client has a part of.
+pub fn if_range_matches(request: &Request, etag: &str, modified: Option<SystemTime>) -> bool {
+    let if_range = match request.headers.get("If-Range") {
+        Some(if_range) => if_range,
+        None => return true,
+    };
+
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return etag_eq(if_range, etag, false);
+    }
+
+    match (parse_http_date(if_range), modified) {
+        (Some(date), Some(modified)) => truncate_to_secs(modified) == date,
+        _ => false,
+    }
+}
@@ -0,0 +1,152 @@
+//! Parser for the runtime configuration file.
+//!
+//! The format is a small subset of INI/TOML:
+//!
+//! ```text
+//! # Comments start with '#' or ';' and must be on their own line.
+//! [server]
+//! addr = 127.0.0.1
+//! port = 8080
+//! allowed_methods = GET, HEAD
+//! extra_headers = "Server: vrs, X-Powered-By: Rust"
+//! ```
+//!
+//! Section headers are only there to help organising the file and have no effect on the keys.
+//! Values may optionally be wrapped in double quotes.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IOError(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IOError(e) => write!(f, "failed reading the configuration file: {e}"),
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        Self::IOError(e)
+    }
+}
+
+/// A single `key = value` pair of the configuration file.
+pub struct Entry {
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+/// Splits the configuration file into its entries without interpreting the values.
+pub fn parse(source: &str) -> Result<Vec<Entry>, ConfigError> {
+    let mut entries = vec![];
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if !line.ends_with(']') || line.len() < 3 {
+                return Err(ConfigError::Parse {
+                    line: line_number,
+                    message: format!("invalid section header '{line}'"),
+                });
+            }
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                return Err(ConfigError::Parse {
+                    line: line_number,
+                    message: format!("expected 'key = value', found '{line}'"),
+                })
+            }
+        };
+
+        if key.is_empty() {
+            return Err(ConfigError::Parse {
+                line: line_number,
+                message: "missing key before '='".to_string(),
+            });
+        }
+
+        entries.push(Entry {
+            line: line_number,
+            key: key.to_string(),
+            value: unquote(value).to_string(),
+        });
+    }
+
+    Ok(entries)
+}
+
+fn unquote(value: &str) -> &str {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+pub fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("expected 'true' or 'false', found '{value}'")),
+    }
+}
+
+pub fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a positive whole number, found '{value}'"))
+}
+
+/// Paths are stored without a trailing slash since the server appends its own.
+pub fn parse_path(value: &str) -> Result<String, String> {
+    if value.is_empty() {
+        return Err("path must not be empty".to_string());
+    }
+
+    match value.trim_end_matches('/') {
+        "" => Ok("/".to_string()),
+        path => Ok(path.to_string()),
+    }
+}
+
+/// Comma separated list of values. Empty items are ignored.
+pub fn parse_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+}
+
+/// Comma separated list of `Name: value` pairs.
+pub fn parse_headers(value: &str) -> Result<Vec<(String, String)>, String> {
+    parse_list(value)
+        .map(|header| match header.split_once(':') {
+            Some((key, val)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), val.trim().to_string()))
+            }
+            _ => Err(format!("expected 'Name: value' header, found '{header}'")),
+        })
+        .collect()
+}
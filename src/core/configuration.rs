@@ -1,95 +1,394 @@
+use super::config_file::{self, ConfigError};
+use super::rules::Rule;
+use super::uri::find_urn;
+use crate::access_log::LogFormat;
 use crate::configuration::*;
-use crate::http::HttpProtocolVersion;
+use crate::http::{HttpProtocolVersion, Method};
+use crate::mime::MimeTypes;
+use crate::response::FALLBACK_PAGES;
 use std::collections::HashSet;
+use std::path::Path;
 
-pub struct Configuration<'a> {
-    pub absolute_static_content_path: &'a str,
-    pub absolute_logs_path: &'a str,
+#[derive(Clone)]
+pub struct Configuration {
+    pub absolute_static_content_path: String,
+    pub absolute_logs_path: String,
     pub save_logs: bool,
-    pub addr: &'a str,
+    pub log_format: LogFormat,
+    pub log_max_size: u64,
+    pub log_rotate_daily: bool,
+    pub log_max_files: usize,
+    pub log_compress: bool,
+    pub addr: String,
     pub port: u32,
     pub multithreading: bool,
     pub num_of_threads: usize,
     pub http_protocol_version: HttpProtocolVersion,
-    pub allowed_methods: HashSet<&'a str>,
+    pub allowed_methods: HashSet<Method>,
     pub allow_all_origins: bool,
-    pub allowed_origins: HashSet<&'a str>,
+    pub allowed_origins: HashSet<String>,
+    pub cors_allowed_headers: Vec<String>,
+    pub cors_expose_headers: Vec<String>,
+    pub cors_max_age: u64,
+    pub cors_allow_credentials: bool,
     pub use_security_headers: bool,
     pub use_time_header: bool,
     pub allow_iframes: bool,
     pub append_extra_headers: bool,
-    pub extra_headers: Vec<(&'a str, &'a str)>,
+    pub extra_headers: Vec<(String, String)>,
+    pub index_files: Vec<String>,
     pub allow_directory_listing: bool,
+    pub clean_urls: bool,
+    pub clean_urls_redirect: bool,
+    pub spa_fallback: bool,
+    pub spa_fallback_file: String,
+    pub spa_include_prefixes: Vec<String>,
+    pub spa_exclude_prefixes: Vec<String>,
+    pub rules: Vec<Rule>,
     pub cache_files: bool,
+    pub cache_max_file_size: u64,
+    pub cache_size: usize,
+    pub cache_revalidate_after: u64,
     pub format_directory_listing_as_json: bool,
     pub print_license_info_at_start: bool,
+    pub keep_alive: bool,
+    pub keep_alive_timeout: u64,
+    pub keep_alive_max_requests: usize,
+    pub mime_types: MimeTypes,
+    pub compression: bool,
+    pub compression_min_size: usize,
+    pub compression_mime_types: Vec<String>,
+    pub serve_precompressed: bool,
 }
 
-// Cloning is required by the `Server`.
-impl<'a> Clone for Configuration<'a> {
-    fn clone(&self) -> Self {
-        Self {
-            allowed_methods: self.allowed_methods.clone(),
-            allowed_origins: self.allowed_origins.clone(),
-            extra_headers: self.extra_headers.clone(),
-            ..*self
-        }
+impl Default for Configuration {
+    fn default() -> Self {
+        Self::read_from_vars()
     }
 }
 
-impl<'a> Configuration<'a> {
+impl Configuration {
     /// Will automatically set the configuration according to the configuration variables set in
     /// `src/configuration.rs`.
     pub fn read_from_vars() -> Self {
         Self {
-            absolute_static_content_path: ABSOLUTE_STATIC_CONTENT_PATH,
-            absolute_logs_path: ABSOLUTE_LOGS_PATH,
+            absolute_static_content_path: ABSOLUTE_STATIC_CONTENT_PATH.to_string(),
+            absolute_logs_path: ABSOLUTE_LOGS_PATH.to_string(),
             save_logs: SAVE_LOGS,
-            addr: ADDR,
+            log_format: LOG_FORMAT,
+            log_max_size: LOG_MAX_SIZE,
+            log_rotate_daily: LOG_ROTATE_DAILY,
+            log_max_files: LOG_MAX_FILES,
+            log_compress: LOG_COMPRESS,
+            addr: ADDR.to_string(),
             port: PORT,
             multithreading: MULTITHREADING,
             num_of_threads: NUM_OF_THREADS,
             http_protocol_version: HTTP_PROTOCOL_VERSION,
             allowed_methods: ALLOWED_METHODS.into(),
             allow_all_origins: ALLOW_ALL_ORIGINS,
-            allowed_origins: ALLOWED_ORIGINS.into(),
+            allowed_origins: ALLOWED_ORIGINS.iter().map(|s| s.to_string()).collect(),
+            cors_allowed_headers: CORS_ALLOWED_HEADERS.iter().map(|s| s.to_string()).collect(),
+            cors_expose_headers: CORS_EXPOSE_HEADERS.iter().map(|s| s.to_string()).collect(),
+            cors_max_age: CORS_MAX_AGE,
+            cors_allow_credentials: CORS_ALLOW_CREDENTIALS,
             use_security_headers: USE_SECURITY_HEADERS,
             allow_iframes: ALLOW_IFRAMES,
             append_extra_headers: APPEND_EXTRA_HEADERS,
-            extra_headers: EXTRA_HEADERS.into(),
+            extra_headers: EXTRA_HEADERS
+                .iter()
+                .map(|(key, val)| (key.to_string(), val.to_string()))
+                .collect(),
+            index_files: INDEX_FILES.iter().map(|s| s.to_string()).collect(),
             allow_directory_listing: ALLOW_DIRECTORY_LISTING,
+            clean_urls: CLEAN_URLS,
+            clean_urls_redirect: CLEAN_URLS_REDIRECT,
+            spa_fallback: SPA_FALLBACK,
+            spa_fallback_file: SPA_FALLBACK_FILE.to_string(),
+            spa_include_prefixes: SPA_INCLUDE_PREFIXES.iter().map(|s| s.to_string()).collect(),
+            spa_exclude_prefixes: SPA_EXCLUDE_PREFIXES.iter().map(|s| s.to_string()).collect(),
+            rules: vec![],
             use_time_header: USE_TIME_HEADER,
             cache_files: CACHE_FILES,
+            cache_max_file_size: CACHE_MAX_FILE_SIZE,
+            cache_size: CACHE_SIZE,
+            cache_revalidate_after: CACHE_REVALIDATE_AFTER,
             format_directory_listing_as_json: FORMAT_DIRECTORY_LISTING_AS_JSON,
             print_license_info_at_start: PRINT_LICENSE_INFO_AT_START,
+            keep_alive: KEEP_ALIVE,
+            keep_alive_timeout: KEEP_ALIVE_TIMEOUT,
+            keep_alive_max_requests: KEEP_ALIVE_MAX_REQUESTS,
+            mime_types: MimeTypes::new(),
+            compression: COMPRESSION,
+            compression_min_size: COMPRESSION_MIN_SIZE,
+            compression_mime_types: COMPRESSION_MIME_TYPES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            serve_precompressed: SERVE_PRECOMPRESSED,
+        }
+    }
+
+    /// Reads the configuration file at `path`. Every option which isn't set in the file keeps the
+    /// default value from `src/configuration.rs`.
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path)?;
+        Self::read_from_str(&source)
+    }
+
+    /// Same as `read_from_file` but for configuration which is already in memory.
+    pub fn read_from_str(source: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::read_from_vars();
+
+        for entry in config_file::parse(source)? {
+            config
+                .set(&entry.key, &entry.value)
+                .map_err(|message| ConfigError::Parse {
+                    line: entry.line,
+                    message,
+                })?;
+        }
+
+        Ok(config)
+    }
+
+    /// Sets a single option by its key as it is written in the configuration file.
+    ///
+    /// Returns a human readable message if the key doesn't exist or the value is invalid.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "absolute_static_content_path" => {
+                self.absolute_static_content_path = config_file::parse_path(value)?
+            }
+            "absolute_logs_path" => self.absolute_logs_path = config_file::parse_path(value)?,
+            "save_logs" => self.save_logs = config_file::parse_bool(value)?,
+            "log_format" => self.log_format = value.parse()?,
+            "log_max_size" => self.log_max_size = config_file::parse_number(value)?,
+            "log_rotate_daily" => self.log_rotate_daily = config_file::parse_bool(value)?,
+            "log_max_files" => self.log_max_files = config_file::parse_number(value)?,
+            "log_compress" => self.log_compress = config_file::parse_bool(value)?,
+            "addr" => self.addr = value.to_string(),
+            "port" => self.port = config_file::parse_number(value)?,
+            "multithreading" => self.multithreading = config_file::parse_bool(value)?,
+            "num_of_threads" => {
+                self.num_of_threads = config_file::parse_number(value)?;
+                if self.num_of_threads == 0 {
+                    return Err("'num_of_threads' must be at least 1".to_string());
+                }
+            }
+            "http_protocol_version" => self.http_protocol_version = value.parse()?,
+            "allowed_methods" => {
+                self.allowed_methods = config_file::parse_list(value)
+                    .map(|method| method.to_uppercase().parse())
+                    .collect::<Result<_, _>>()?;
+            }
+            "allow_all_origins" => self.allow_all_origins = config_file::parse_bool(value)?,
+            "allowed_origins" => {
+                self.allowed_origins = config_file::parse_list(value)
+                    .map(|origin| origin.to_string())
+                    .collect();
+            }
+            "cors_allowed_headers" => {
+                self.cors_allowed_headers = config_file::parse_list(value)
+                    .map(|header| header.to_string())
+                    .collect();
+            }
+            "cors_expose_headers" => {
+                self.cors_expose_headers = config_file::parse_list(value)
+                    .map(|header| header.to_string())
+                    .collect();
+            }
+            "cors_max_age" => self.cors_max_age = config_file::parse_number(value)?,
+            "cors_allow_credentials" => {
+                self.cors_allow_credentials = config_file::parse_bool(value)?
+            }
+            "use_security_headers" => self.use_security_headers = config_file::parse_bool(value)?,
+            "use_time_header" => self.use_time_header = config_file::parse_bool(value)?,
+            "allow_iframes" => self.allow_iframes = config_file::parse_bool(value)?,
+            "append_extra_headers" => self.append_extra_headers = config_file::parse_bool(value)?,
+            "extra_headers" => self.extra_headers = config_file::parse_headers(value)?,
+            "index_files" => {
+                self.index_files = config_file::parse_list(value)
+                    .map(|index_file| index_file.to_string())
+                    .collect();
+            }
+            "clean_urls" => self.clean_urls = config_file::parse_bool(value)?,
+            "clean_urls_redirect" => self.clean_urls_redirect = config_file::parse_bool(value)?,
+            "spa_fallback" => self.spa_fallback = config_file::parse_bool(value)?,
+            "spa_fallback_file" => {
+                // Stored the way request paths are, relative to the static content directory.
+                let path = format!("/{}", value.trim_start_matches('/'));
+
+                self.spa_fallback_file = match find_urn(&path) {
+                    Some(urn) if !urn.is_empty() && !urn.ends_with('/') => urn,
+                    _ => return Err(format!("'{value}' isn't a valid file path")),
+                }
+            }
+            "spa_include_prefixes" => {
+                self.spa_include_prefixes = config_file::parse_list(value)
+                    .map(|prefix| prefix.to_string())
+                    .collect();
+            }
+            "spa_exclude_prefixes" => {
+                self.spa_exclude_prefixes = config_file::parse_list(value)
+                    .map(|prefix| prefix.to_string())
+                    .collect();
+            }
+            // Every `redirect` and `rewrite` adds a rule after the ones before it.
+            "redirect" => self.rules.push(Rule::parse_redirect(value)?),
+            "rewrite" => self.rules.push(Rule::parse_rewrite(value)?),
+            "allow_directory_listing" => {
+                self.allow_directory_listing = config_file::parse_bool(value)?
+            }
+            "cache_files" => self.cache_files = config_file::parse_bool(value)?,
+            "cache_max_file_size" => {
+                self.cache_max_file_size = config_file::parse_number(value)?
+            }
+            "cache_size" => self.cache_size = config_file::parse_number(value)?,
+            "cache_revalidate_after" => {
+                self.cache_revalidate_after = config_file::parse_number(value)?
+            }
+            "format_directory_listing_as_json" => {
+                self.format_directory_listing_as_json = config_file::parse_bool(value)?
+            }
+            "print_license_info_at_start" => {
+                self.print_license_info_at_start = config_file::parse_bool(value)?
+            }
+            "keep_alive" => self.keep_alive = config_file::parse_bool(value)?,
+            "keep_alive_timeout" => self.keep_alive_timeout = config_file::parse_number(value)?,
+            "keep_alive_max_requests" => {
+                self.keep_alive_max_requests = config_file::parse_number(value)?
+            }
+            "compression" => self.compression = config_file::parse_bool(value)?,
+            "compression_min_size" => {
+                self.compression_min_size = config_file::parse_number(value)?
+            }
+            "compression_mime_types" => {
+                self.compression_mime_types = config_file::parse_list(value)
+                    .map(|mime_type| mime_type.to_ascii_lowercase())
+                    .collect();
+            }
+            "serve_precompressed" => {
+                self.serve_precompressed = config_file::parse_bool(value)?
+            }
+            "mime_types_file" => {
+                self.mime_types = MimeTypes::read_from_file(config_file::parse_path(value)?)
+                    .map_err(|e| format!("failed loading '{value}': {e}"))?
+            }
+            _ => return Err(format!("unknown configuration key '{key}'")),
+        }
+
+        Ok(())
+    }
+
+    /// Looks for problems that would stop the server from starting or from answering requests
+    /// properly, such as a missing static directory or fallback pages.
+    ///
+    /// Returns a description of every problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        if self.port > u16::MAX as u32 {
+            problems.push(format!("port {} is out of range", self.port));
+        }
+
+        if self.num_of_threads == 0 {
+            problems.push("'num_of_threads' must be at least 1".to_string());
+        }
+
+        if self.allowed_methods.is_empty() {
+            problems.push("'allowed_methods' must contain at least one method".to_string());
         }
+
+        let static_path = Path::new(&self.absolute_static_content_path);
+
+        if !static_path.is_dir() {
+            problems.push(format!(
+                "static content directory '{}' doesn't exist",
+                self.absolute_static_content_path
+            ));
+        } else {
+            for status_code in FALLBACK_PAGES {
+                let page = static_path.join(format!("{status_code}.html"));
+                if !page.is_file() {
+                    problems.push(format!("fallback page '{}' doesn't exist", page.display()));
+                }
+            }
+        }
+
+        if self.spa_fallback && static_path.is_dir() {
+            let file = static_path.join(&self.spa_fallback_file);
+            if !file.is_file() {
+                problems.push(format!("SPA fallback file '{}' doesn't exist", file.display()));
+            }
+        }
+
+        if self.save_logs && !Path::new(&self.absolute_logs_path).is_dir() {
+            problems.push(format!(
+                "logs directory '{}' doesn't exist",
+                self.absolute_logs_path
+            ));
+        }
+
+        problems
     }
 
     pub fn test_config() -> Self {
         Configuration {
-            absolute_logs_path: ABSOLUTE_LOGS_PATH,
-            absolute_static_content_path: "media",
-            addr: "localhost",
+            absolute_logs_path: ABSOLUTE_LOGS_PATH.to_string(),
+            absolute_static_content_path: "media".to_string(),
+            addr: "localhost".to_string(),
             // Setting the port to 0 takes advantage of an OS behavior that
             // always uses a free port when assigned in this manner on all
             // major platforms.
             port: 0,
             allow_all_origins: false,
             allow_iframes: false,
-            allowed_methods: HashSet::from(["GET"]),
-            allowed_origins: HashSet::from(["localhost"]),
+            allowed_methods: HashSet::from([Method::Get, Method::Head]),
+            allowed_origins: HashSet::from(["localhost".to_string()]),
+            cors_allowed_headers: vec![],
+            cors_expose_headers: vec![],
+            cors_max_age: 0,
+            cors_allow_credentials: false,
             save_logs: false,
+            log_format: LogFormat::Combined,
+            log_max_size: 0,
+            log_rotate_daily: false,
+            log_max_files: 0,
+            log_compress: false,
             multithreading: false,
             num_of_threads: 1,
             http_protocol_version: HttpProtocolVersion::OneDotOne,
             use_security_headers: false,
             append_extra_headers: false,
             extra_headers: vec![],
+            index_files: vec!["index.html".to_string()],
             allow_directory_listing: true,
+            clean_urls: false,
+            clean_urls_redirect: false,
+            spa_fallback: false,
+            spa_fallback_file: "index.html".to_string(),
+            spa_include_prefixes: vec!["/".to_string()],
+            spa_exclude_prefixes: vec![],
+            rules: vec![],
             use_time_header: false,
             cache_files: false,
+            cache_max_file_size: 10 * 1024 * 1024,
+            cache_size: 64 * 1024 * 1024,
+            cache_revalidate_after: 0,
             format_directory_listing_as_json: false,
             print_license_info_at_start: false,
+            keep_alive: true,
+            keep_alive_timeout: 5,
+            keep_alive_max_requests: 100,
+            mime_types: MimeTypes::new(),
+            compression: true,
+            compression_min_size: 1024,
+            compression_mime_types: COMPRESSION_MIME_TYPES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            serve_precompressed: true,
         }
     }
 }
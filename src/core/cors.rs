@@ -0,0 +1,94 @@
+use super::configuration::Configuration;
+use crate::headers::Headers;
+use crate::http::Method;
+use crate::request::Request;
+
+/// Returns the value of `Access-Control-Allow-Origin` for the request's origin, or `None` if the
+/// origin isn't allowed.
+fn allowed_origin<'a>(config: &Configuration, origin: Option<&'a str>) -> Option<&'a str> {
+    match origin {
+        // Browsers refuse "*" for requests with credentials, so the origin has to be echoed.
+        Some(origin) if config.allow_all_origins && config.cors_allow_credentials => Some(origin),
+        _ if config.allow_all_origins => Some("*"),
+        Some(origin) if config.allowed_origins.contains(origin) => Some(origin),
+        _ => None,
+    }
+}
+
+/// CORS headers sent with every response.
+pub fn cors_headers(config: &Configuration, request: &Request) -> Headers {
+    let mut headers = Headers::new();
+
+    // Unless every origin gets "*", the response depends on the origin (echoed back or rejected),
+    // caches must not reuse it for other origins.
+    if !config.allow_all_origins || config.cors_allow_credentials {
+        headers.append("Vary", "Origin");
+    }
+
+    let allowed = match allowed_origin(config, request.headers.get("Origin")) {
+        Some(allowed) => allowed,
+        None => {
+            headers.insert("Access-Control-Allow-Origin", "null");
+            return headers;
+        }
+    };
+
+    headers.insert("Access-Control-Allow-Origin", allowed);
+
+    if config.cors_allow_credentials {
+        headers.insert("Access-Control-Allow-Credentials", "true");
+    }
+
+    if !config.cors_expose_headers.is_empty() {
+        headers.insert(
+            "Access-Control-Expose-Headers",
+            config.cors_expose_headers.join(", "),
+        );
+    }
+
+    headers
+}
+
+/// Whether the request is a CORS preflight request rather than a plain OPTIONS request.
+pub fn is_preflight(request: &Request) -> bool {
+    request.method == Method::Options
+        && request.headers.contains("Origin")
+        && request.headers.contains("Access-Control-Request-Method")
+}
+
+/// Headers answering an OPTIONS request. Preflight requests from allowed origins additionally get
+/// the `Access-Control-Allow-*` headers describing what the actual request may use.
+pub fn options_headers(config: &Configuration, request: &Request) -> Headers {
+    let mut headers = cors_headers(config, request);
+
+    let mut methods: Vec<&str> = config
+        .allowed_methods
+        .iter()
+        .map(|method| method.as_str())
+        .chain(std::iter::once(Method::Options.as_str()))
+        .collect();
+    methods.sort_unstable();
+    methods.dedup();
+    let methods = methods.join(", ");
+
+    headers.insert("Allow", methods.as_str());
+
+    if !is_preflight(request) || allowed_origin(config, request.headers.get("Origin")).is_none() {
+        return headers;
+    }
+
+    headers.insert("Access-Control-Allow-Methods", methods);
+
+    if !config.cors_allowed_headers.is_empty() {
+        headers.insert(
+            "Access-Control-Allow-Headers",
+            config.cors_allowed_headers.join(", "),
+        );
+    }
+
+    if config.cors_max_age > 0 {
+        headers.insert("Access-Control-Max-Age", config.cors_max_age.to_string());
+    }
+
+    headers
+}
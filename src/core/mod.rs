@@ -1,4 +1,7 @@
+pub mod conditional;
+pub mod config_file;
 pub mod configuration;
+pub mod cors;
+pub mod rules;
 pub mod server;
-pub mod socket;
 pub mod uri;
@@ -0,0 +1,229 @@
+use crate::request::Request;
+use crate::status::StatusCode;
+
+/// What a matching rule does with the request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RuleKind {
+    /// Sends the client to the target with one of the redirect status codes.
+    Redirect(StatusCode),
+    /// Serves the target instead of the requested path, without the client knowing about it.
+    Rewrite,
+}
+
+/// The outcome of the first rule matching a request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleAction {
+    Redirect {
+        status: StatusCode,
+        location: String,
+    },
+    /// The path to serve instead.
+    Rewrite(String),
+}
+
+/// A redirect or rewrite rule.
+///
+/// The pattern is matched against the whole path of the request as it was sent, before
+/// percent-decoding. A pattern without `*` only matches that exact path, every `*` matches any
+/// sequence of characters (including `/`) and can be referred to in the target as `$1`, `$2`, ...
+/// in order of appearance. `/blog/*` matches every path below `/blog/`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    /// The pattern split at every `*`.
+    parts: Vec<String>,
+    target: String,
+    kind: RuleKind,
+}
+
+impl Rule {
+    /// Creates a rule sending clients from paths matching `pattern` to `target`, which is either an
+    /// absolute path or an `http(s)://` URL.
+    pub fn redirect(pattern: &str, target: &str, status: StatusCode) -> Result<Self, String> {
+        if !matches!(
+            status,
+            StatusCode::MovedPermanently
+                | StatusCode::Found
+                | StatusCode::TemporaryRedirect
+                | StatusCode::PermanentRedirect
+        ) {
+            return Err(format!(
+                "'{}' isn't a redirect status, expected 301, 302, 307 or 308",
+                status as u16
+            ));
+        }
+
+        let is_url = ["http://", "https://"]
+            .iter()
+            .any(|scheme| target.starts_with(scheme));
+
+        if !target.starts_with('/') && !is_url {
+            return Err(format!(
+                "redirect target '{target}' must start with '/' or be an http(s) URL"
+            ));
+        }
+
+        Self::new(pattern, target, RuleKind::Redirect(status))
+    }
+
+    /// Creates a rule serving `target`, an absolute path, for paths matching `pattern`.
+    pub fn rewrite(pattern: &str, target: &str) -> Result<Self, String> {
+        if !target.starts_with('/') {
+            return Err(format!("rewrite target '{target}' must start with '/'"));
+        }
+
+        // Files are served the same whatever the query is.
+        if target.contains('?') {
+            return Err(format!("rewrite target '{target}' can't have a query"));
+        }
+
+        Self::new(pattern, target, RuleKind::Rewrite)
+    }
+
+    fn new(pattern: &str, target: &str, kind: RuleKind) -> Result<Self, String> {
+        if !pattern.starts_with('/') {
+            return Err(format!("pattern '{pattern}' must start with '/'"));
+        }
+
+        let parts: Vec<String> = pattern.split('*').map(str::to_string).collect();
+        let captures = parts.len() - 1;
+
+        // Every `$n` in the target needs a matching `*`.
+        let mut chars = target.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                continue;
+            }
+
+            match chars.next().and_then(|c| c.to_digit(10)) {
+                Some(n) if n >= 1 && n as usize <= captures => {}
+                _ => return Err(format!(
+                    "target '{target}' refers to a capture which pattern '{pattern}' doesn't have"
+                )),
+            }
+        }
+
+        Ok(Self {
+            parts,
+            target: target.to_string(),
+            kind,
+        })
+    }
+
+    /// Parses `<pattern> <target> [status]`, as written in the configuration file. The status
+    /// defaults to 301.
+    pub fn parse_redirect(value: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+
+        let (pattern, target, status) = match fields.as_slice() {
+            [pattern, target] => (*pattern, *target, StatusCode::MovedPermanently),
+            [pattern, target, status] => {
+                let status = status
+                    .parse::<u16>()
+                    .ok()
+                    .and_then(|status| StatusCode::try_from(status).ok())
+                    .ok_or_else(|| format!("invalid redirect status '{status}'"))?;
+
+                (*pattern, *target, status)
+            }
+            _ => {
+                return Err(format!(
+                    "expected '<pattern> <target> [status]', found '{value}'"
+                ))
+            }
+        };
+
+        Self::redirect(pattern, target, status)
+    }
+
+    /// Parses `<pattern> <target>`, as written in the configuration file.
+    pub fn parse_rewrite(value: &str) -> Result<Self, String> {
+        match value.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [pattern, target] => Self::rewrite(pattern, target),
+            _ => Err(format!("expected '<pattern> <target>', found '{value}'")),
+        }
+    }
+
+    /// Returns what the rule does with a request for `path` and `query`, or `None` if it doesn't
+    /// match.
+    pub fn apply(&self, path: &str, query: Option<&str>) -> Option<RuleAction> {
+        let captures = self.captures(path)?;
+
+        let mut target = String::new();
+        let mut chars = self.target.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '$' => {
+                    // Validated when the rule was created.
+                    let n = chars.next().and_then(|c| c.to_digit(10)).unwrap_or(1);
+                    target.push_str(captures[n as usize - 1]);
+                }
+                c => target.push(c),
+            }
+        }
+
+        // A capture at the start of a path mustn't turn it into `//host` (or `/\host`), which
+        // clients treat as a URL on another host.
+        if self.target.starts_with('/') {
+            target = format!("/{}", target.trim_start_matches(['/', '\\']));
+        }
+
+        Some(match self.kind {
+            RuleKind::Redirect(status) => {
+                // The query of the request is kept unless the target has its own.
+                if let (Some(query), false) = (query, target.contains('?')) {
+                    target.push('?');
+                    target.push_str(query);
+                }
+
+                RuleAction::Redirect {
+                    status,
+                    location: target,
+                }
+            }
+            RuleKind::Rewrite => RuleAction::Rewrite(target),
+        })
+    }
+
+    /// Matches `path` against the pattern and returns what every `*` matched.
+    fn captures<'a>(&self, path: &'a str) -> Option<Vec<&'a str>> {
+        let (first, rest) = self.parts.split_first()?;
+
+        let Some((last, middle)) = rest.split_last() else {
+            return (path == first).then(Vec::new);
+        };
+
+        // The first and last parts are anchored, the ones in between are matched as early as
+        // possible.
+        if path.len() < first.len() + last.len() || !path.starts_with(first.as_str()) {
+            return None;
+        }
+
+        let end = path.len() - last.len();
+
+        if &path[end..] != last {
+            return None;
+        }
+
+        let mut captures = vec![];
+        let mut pos = first.len();
+
+        for part in middle {
+            let found = path.get(pos..end)?.find(part.as_str())?;
+            captures.push(&path[pos..pos + found]);
+            pos += found + part.len();
+        }
+
+        captures.push(path.get(pos..end)?);
+
+        Some(captures)
+    }
+}
+
+/// Returns the action of the first rule matching `request`.
+pub fn apply_rules(rules: &[Rule], request: &Request) -> Option<RuleAction> {
+    rules
+        .iter()
+        .find_map(|rule| rule.apply(&request.path, request.query.as_deref()))
+}
@@ -1,21 +1,34 @@
 use super::configuration::Configuration;
+use crate::access_log::AccessLogEntry;
 use crate::response::response_builder::ResponseBuilder;
-use super::socket::{parse_utf8, read_stream};
+use super::conditional::{evaluate_preconditions, if_range_matches};
+use super::cors;
+use super::rules::{apply_rules, RuleAction};
 use super::uri::*;
+use crate::cache::{FileCache, Lookup};
 use crate::compile_if_eq;
+use crate::compression::{self, negotiate, Encoding};
 use crate::error::ServerError;
-use crate::file::{get_file_extension, CachedFile};
+use crate::file::{generate_etag, get_file_extension, variant_etag, CachedFile, StreamedFile};
+use crate::headers::Headers;
+use crate::http::Method;
+use crate::logger::{LogRotation, Logger};
+use crate::range::{parse_range, RangeResult};
+use crate::request::Request;
 use crate::response::types::*;
+use crate::response::Response;
 use crate::state::AppState;
+use crate::status::StatusCode;
 use crate::thread::ThreadPool;
-use crate::time::generate_unixtime;
-use std::collections::HashMap;
-use std::fs::{self, File};
+use crate::time::format_http_date;
+use std::fs;
 use std::io;
-use std::io::{Read, Write};
+use std::io::{BufRead, BufReader, Read};
+use std::net::{IpAddr, TcpStream};
 use std::path::Path;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::{fs::OpenOptions, net::TcpListener};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use std::net::TcpListener;
 
 /// Function executed during server initialization for initial server tasks such as printing the software license's information.
 fn do_initial_tasks(config: &Configuration) {
@@ -27,65 +40,93 @@ fn do_initial_tasks(config: &Configuration) {
 /// The main initializer for the server used by the single-threaded and multi-threaded initializers.
 ///
 /// The `init` closure additionally takes a `ThreadPool` which can be disregarded if the server isn't planning to use multiple threads.
-fn server_initializer<F: FnOnce(ThreadPool, TcpListener, AppState) -> Result<(), ServerError>>(
+fn server_initializer<
+    F: FnOnce(ThreadPool, TcpListener, AppState, Option<Logger>) -> Result<(), ServerError>,
+>(
     config: &Configuration,
+    listener: TcpListener,
     init: F,
 ) -> Result<(), ServerError> {
     let pool = ThreadPool::new(config.num_of_threads)?;
 
     do_initial_tasks(config);
 
-    let listener =
-        TcpListener::bind(format!("{}:{}", config.addr, config.port))?;
+    let state = AppState::new(config);
 
-    let state = AppState::default();
+    let logger = match config.save_logs {
+        true => open_logger(config),
+        false => None,
+    };
 
-    init(pool, listener, state)
+    init(pool, listener, state, logger)
 }
 
-pub fn start_multithread(config: Configuration) -> Result<(), ServerError> {
-    server_initializer(&config, |pool, listener, mut state| {
-        if !config.use_security_headers {
-            println!("Production note: security headers are currently turned off, keep it enabled in production!")
-        }
+/// Creating a file with this name in the logs directory makes a running server reopen
+/// `access.log`.
+pub const LOG_REOPEN_TRIGGER: &str = "reopen-logs";
 
-        if config.cache_files {
-            state.cached_files = Some(HashMap::new());
+/// Starts logging to `access.log` in the logs directory.
+///
+/// Logging is optional, so failing to open the file only disables it.
+fn open_logger(config: &Configuration) -> Option<Logger> {
+    let rotation = LogRotation {
+        max_size: config.log_max_size,
+        daily: config.log_rotate_daily,
+        max_files: config.log_max_files,
+        compress: config.log_compress,
+    };
+
+    match Logger::open(format!("{}/access.log", config.absolute_logs_path), rotation) {
+        Ok(logger) => {
+            logger.watch(format!("{}/{LOG_REOPEN_TRIGGER}", config.absolute_logs_path));
+            Some(logger)
         }
+        Err(_) => {
+            println!("Warning: Failed creating or opening logfile. Logs will not be saved.");
+            None
+        }
+    }
+}
 
-        let (cache_tx, cache_rx): (
-            Sender<HashMap<String, CachedFile>>,
-            Receiver<HashMap<String, CachedFile>>,
-        ) = mpsc::channel();
+/// Asks the server running with `config` to reopen its log file within a second, by creating the
+/// trigger file in the logs directory.
+pub fn request_log_reopen(config: &Configuration) -> io::Result<()> {
+    fs::write(format!("{}/{LOG_REOPEN_TRIGGER}", config.absolute_logs_path), "")
+}
 
-        for stream in listener.incoming() {
-            let state_ref = state.clone();
-            let cache_tx_ref = cache_tx.clone();
+/// Binds the listener on the address and port from `config`.
+fn bind(config: &Configuration) -> Result<TcpListener, ServerError> {
+    Ok(TcpListener::bind(format!("{}:{}", config.addr, config.port))?)
+}
 
-            pool.execute(|| {
-                let config = Configuration::test_config();
+pub fn start_multithread(config: Configuration) -> Result<(), ServerError> {
+    let listener = bind(&config)?;
+    serve_multithread(config, listener)
+}
 
-                let mut stream = stream.unwrap();
-                let mut state_ref = state_ref;
-                let tx = cache_tx_ref;
+/// Runs the multithreaded server on an already bound `listener`, such as one bound to an ephemeral
+/// port. The address and port from `config` are ignored.
+pub fn serve_multithread(config: Configuration, listener: TcpListener) -> Result<(), ServerError> {
+    // Every job gets a handle to the same configuration.
+    let config = Arc::new(config);
 
-                let response = serve_request(&config, None, &mut stream, &mut state_ref);
+    server_initializer(&config, listener, |pool, listener, state, logger| {
+        if !config.use_security_headers {
+            println!("Production note: security headers are currently turned off, keep it enabled in production!")
+        }
 
-                // Note: `.unwrap()` will only make one of the threads panic in multithreaded mode, so unwrapping instead of returning the error is fine.
-                stream.write_all(&response.unwrap().as_bytes()).unwrap();
-                stream.flush().unwrap();
+        for stream in listener.incoming() {
+            // The file cache is shared, workers read and populate it directly.
+            let state_ref = state.clone();
+            let config_ref = Arc::clone(&config);
+            let logger_ref = logger.clone();
 
-                if config.cache_files {
-                    tx.send(state_ref.cached_files.expect(
-                        "'cached_files' is None even though 'cache_files' boolean is set to true.",
-                    ))
-                    .expect("Failed transmitting app's state.");
+            pool.execute(move || {
+                // A connection which failed before it was accepted has nothing to answer.
+                if let Ok(stream) = stream {
+                    serve_connection(&config_ref, logger_ref.as_ref(), &stream, &state_ref);
                 }
             });
-
-            if config.cache_files {
-                state.cached_files = Some(cache_rx.recv().unwrap());
-            }
         }
 
         Ok(())
@@ -93,143 +134,258 @@ pub fn start_multithread(config: Configuration) -> Result<(), ServerError> {
 }
 
 pub fn start_singlethread(config: Configuration) -> Result<(), ServerError> {
-    server_initializer(&config, |_, listener, mut state| {
-        let unix_ts = generate_unixtime()?;
-
-        /* Create the log file and return error if it fails creating or opening existing one */
-        let mut logfile =
-            if config.save_logs {
-                let result =
-                    Some(OpenOptions::new().append(true).create(true).open(
-                        [config.absolute_logs_path, "/", unix_ts.to_string().as_str()].concat(),
-                    ));
-                match result.expect("Something went wrong whilst unwrapping the logfile.") {
-                    Ok(file) => Some(file),
-                    Err(_) => {
-                        println!(
-                            "Warning: Failed creating or opening logfile. Logs will not be saved."
-                        );
-                        None
-                    }
-                }
-            } else {
-                None
-            };
+    let listener = bind(&config)?;
+    serve_singlethread(config, listener)
+}
 
+/// Runs the single-threaded server on an already bound `listener`. The address and port from
+/// `config` are ignored.
+///
+/// Connections are closed after every response, keeping an idle one open would block every other
+/// client until it times out.
+pub fn serve_singlethread(config: Configuration, listener: TcpListener) -> Result<(), ServerError> {
+    let config = Configuration {
+        keep_alive: false,
+        ..config
+    };
+
+    server_initializer(&config, listener, |_, listener, state, logger| {
         if !config.use_security_headers {
             println!("Production note: security headers are currently turned off, keep it enabled in production!");
         }
 
-        if config.cache_files {
-            state.cached_files = Some(HashMap::new());
+        // A connection which failed before it was accepted has nothing to answer.
+        for stream in listener.incoming().flatten() {
+            serve_connection(&config, logger.as_ref(), &stream, &state);
         }
 
-        for stream in listener.incoming() {
-            let mut stream = stream?; /* Note that stream is a result. */
+        Ok(())
+    })
+}
 
-            let response = serve_request(&config, logfile.as_mut(), &stream, &mut state)?;
+/// Serves every request sent on the connection until either side wants to close it, the client
+/// stays idle for longer than `keep_alive_timeout` or `keep_alive_max_requests` is reached.
+///
+/// Errors on the socket itself (e.g. the client going away) only end the connection, they never
+/// reach the caller so that a single client can't stop the server or one of its workers.
+pub fn serve_connection(
+    config: &Configuration,
+    logger: Option<&Logger>,
+    stream: &TcpStream,
+    state: &AppState,
+) {
+    let timeout = Duration::from_secs(config.keep_alive_timeout.max(1));
+
+    // Responses are written in as few writes as possible, there's nothing to gain from delaying
+    // them and waiting for the client's acknowledgement stalls keep-alive connections. Both fail
+    // if the client already reset the connection on some platforms.
+    if stream.set_read_timeout(Some(timeout)).is_err() || stream.set_nodelay(true).is_err() {
+        return;
+    }
+
+    let client = stream.peer_addr().ok().map(|addr| addr.ip());
+    let mut reader = BufReader::new(stream);
+    let mut writer = stream;
+    let mut requests_served = 0;
 
-            stream.write_all(&response.as_bytes())?;
-            stream.flush()?;
+    loop {
+        // Wait for the next request, the client closing the connection or the idle timeout.
+        match reader.fill_buf() {
+            Ok(buf) if !buf.is_empty() => {}
+            _ => break,
         }
 
-        Ok(())
-    })
+        requests_served += 1;
+
+        let allow_keep_alive =
+            config.keep_alive && requests_served < config.keep_alive_max_requests;
+
+        let Ok(mut response) = serve_next_request(
+            config,
+            logger,
+            client,
+            &mut reader,
+            state,
+            allow_keep_alive,
+        ) else {
+            break;
+        };
+
+        if response.write_to(&mut writer).is_err() {
+            break;
+        }
+
+        if !response.keep_alive {
+            break;
+        }
+    }
 }
 
+/// Serves a single request read from `input`.
 pub fn serve_request(
     config: &Configuration,
-    logfile: Option<&mut File>,
-    input: impl Read,
-    state: &mut AppState,
-) -> Result<String, ServerError> {
-    let res = ResponseBuilder { 
-        status_code: 200,
-        req_headers: None,
+    logger: Option<&Logger>,
+    input: impl BufRead,
+    state: &AppState,
+) -> Result<Response, ServerError> {
+    serve_next_request(config, logger, None, input, state, config.keep_alive)
+}
+
+/// Reads a single request from `input`, responds to it and logs it. `client` is the address of the
+/// client if the request came in over a socket.
+fn serve_next_request(
+    config: &Configuration,
+    logger: Option<&Logger>,
+    client: Option<IpAddr>,
+    input: impl BufRead,
+    state: &AppState,
+    allow_keep_alive: bool,
+) -> Result<Response, ServerError> {
+    let time = SystemTime::now();
+    let started = Instant::now();
+
+    let request = Request::read_from(input);
+
+    let response = match &request {
+        // The connection is closed after a 500 since it's unclear what went wrong.
+        Ok(request) => match respond(config, request, state, allow_keep_alive) {
+            Ok(response) => response,
+            Err(_) => response_builder(config)
+                .status_code(StatusCode::InternalServerError)
+                .build()?,
+        },
+        Err(status) => response_builder(config).status_code(*status).build()?,
+    };
+
+    if let Some(logger) = logger {
+        logger.log(config.log_format.format(&AccessLogEntry {
+            client,
+            time,
+            request: request.as_ref().ok(),
+            status: response.status_code,
+            bytes_sent: response.body.len(),
+            latency: started.elapsed(),
+        }));
+    }
+
+    Ok(response)
+}
+
+/// The builder every response starts from.
+fn response_builder(config: &Configuration) -> ResponseBuilder<'_> {
+    let res = ResponseBuilder {
+        status_code: StatusCode::Ok,
+        headers: Headers::new(),
         response_type: None,
         config,
+        keep_alive: false,
+        method: Method::Get,
     };
 
     // Default to fallback response since it's the most common.
-    let res = res.response_type(ResponseType::Fallback);
+    res.response_type(ResponseType::Fallback)
+}
+
+fn respond(
+    config: &Configuration,
+    request: &Request,
+    state: &AppState,
+    allow_keep_alive: bool,
+) -> Result<Response, ServerError> {
+    let res = response_builder(config);
+
+    let keep_alive = allow_keep_alive && request.keep_alive();
+
+    let res = res.keep_alive(keep_alive).method(request.method);
+
+    // OPTIONS is always answered since browsers rely on it for CORS preflight requests.
+    if request.method == Method::Options {
+        return res
+            .headers(cors::options_headers(config, request))
+            .status_code(StatusCode::NoContent)
+            .response_type(ResponseType::Empty)
+            .build();
+    }
+
+    let res = res.headers(cors::cors_headers(config, request));
+
+    if !config.allowed_methods.contains(&request.method) {
+        return res.status_code(StatusCode::MethodNotAllowed).build();
+    }
 
-    let (mut req_headers, buf) = match read_stream(input) {
-        Ok((headers, buf)) => (headers, buf),
-        Err(status) => {
+    // Rules are matched against the path as the client sent it.
+    let rewritten = match apply_rules(&config.rules, request) {
+        Some(RuleAction::Redirect { status, location }) => {
             return res
-                .req_headers(HashMap::new())
+                .header("Location", location)
                 .status_code(status)
+                .response_type(ResponseType::Empty)
                 .build();
         }
+        Some(RuleAction::Rewrite(path)) => Some(path),
+        None => None,
     };
 
-    let origin = match req_headers.get("Origin") {
-        Some(header) => header.to_string(),
-        None => "null".to_string(),
+    let mut urn = match find_urn(rewritten.as_deref().unwrap_or(&request.path)) {
+        Some(urn) => urn,
+        None => return res.status_code(StatusCode::BadRequest).build(),
     };
 
-    match logfile {
-        Some(file) => {
-            match file.write_all(
-                format!(
-                    "
--- NEW REQUEST --
-HEADERS: {:?}
-                    ",
-                    req_headers,
-                )
-                .as_bytes(),
-            ) {
-                Ok(()) => {}
-                Err(_) => {
-                    println!("Warning: something went wrong whilst writing to the logfile. Maybe it's too large?");
+    let mut absolute_path = format!("{}/{urn}", config.absolute_static_content_path);
+
+    if config.clean_urls {
+        let is_file = Path::new(&absolute_path).is_file();
+
+        // Rewritten requests are left alone, the client never asked for the HTML file.
+        if config.clean_urls_redirect && is_file && rewritten.is_none() {
+            if let Some(clean_urn) = clean_url(&urn) {
+                if serves_file(config, clean_urn, &urn) {
+                    return res
+                        .header("Location", location(clean_urn, request))
+                        .status_code(StatusCode::MovedPermanently)
+                        .response_type(ResponseType::Empty)
+                        .build();
                 }
             }
         }
-        None => {}
-    }
 
-    req_headers.insert(
-        "Access-Control-Allow-Origin".to_string(),
-        if config.allow_all_origins {
-            "*".to_string()
-        } else if config.allowed_origins.contains(origin.as_str()) {
-            origin
-        } else {
-            "null".to_string()
-        },
-    );
+        // Files and directories which exist take precedence over the HTML file.
+        if !is_file && !Path::new(&absolute_path).exists() {
+            if let Some(html_file) = html_file_for(&urn) {
+                let html_path = format!("{}/{html_file}", config.absolute_static_content_path);
 
-    let buf_utf8 = match parse_utf8(&req_headers, &buf) {
-        Ok(utf8) => utf8,
-        Err((headers, status)) => {
-            for (key, val) in headers {
-                req_headers.insert(key.to_string(), val.to_string());
+                if Path::new(&html_path).is_file() {
+                    urn = html_file;
+                    absolute_path = html_path;
+                }
             }
+        }
+    }
+
+    if Path::new(&absolute_path).is_dir() {
+        // Relative links in the index file or the listing only work with a trailing slash.
+        if !urn.is_empty() && !urn.ends_with('/') {
             return res
-                .req_headers(req_headers)
-                .status_code(status)
+                .header("Location", location(&format!("{urn}/"), request))
+                .status_code(StatusCode::MovedPermanently)
+                .response_type(ResponseType::Empty)
                 .build();
         }
-    };
-
-    let res = res.req_headers(req_headers);
 
-    let req_method = match buf_utf8.split_whitespace().next() {
-        Some(req_method) => req_method,
-        None => return res.status_code(500).build(),
-    };
-
-    if !config.allowed_methods.contains(req_method) {
-        return res.status_code(405).build();
+        if let Some(index_file) = find_index_file(config, &absolute_path) {
+            urn.push_str(index_file);
+            absolute_path = format!("{}/{urn}", config.absolute_static_content_path);
+        }
     }
 
-    let urn = match find_urn(&buf_utf8) {
-        Some(urn) => urn,
-        None => return res.status_code(400).build(),
-    };
-
-    let absolute_path = format!("{}/{urn}", config.absolute_static_content_path);
+    // Unknown paths of a single-page application are left to its client-side router.
+    if config.spa_fallback
+        && spa_fallback_applies(config, &urn)
+        && !Path::new(&absolute_path).exists()
+    {
+        urn = config.spa_fallback_file.clone();
+        absolute_path = format!("{}/{urn}", config.absolute_static_content_path);
+    }
 
     let path = Path::new(&absolute_path);
 
@@ -237,93 +393,293 @@ HEADERS: {:?}
         if config.allow_directory_listing {
             let path_iterator = match path.read_dir() {
                 Ok(iter) => iter,
-                Err(_) => return res.status_code(500).build(),
+                Err(_) => return res.status_code(StatusCode::InternalServerError).build(),
             };
             return res
                 .response_type(ResponseType::Dir(DirResponse { path_iterator }))
                 .build();
         } else {
-            return res.status_code(404).build();
+            return res.status_code(StatusCode::NotFound).build();
         }
     }
 
+    // The MIME type always comes from the requested file, even if a precompressed file is sent.
     let file_ext = get_file_extension(&urn);
+    let accept_encoding = request.headers.get("Accept-Encoding");
+
+    // Precompressed files next to the requested file, such as `app.js.br`. They're looked up
+    // even if the client doesn't accept any encoding so that `Vary` is the same for every client.
+    let precompressed: Vec<Encoding> = match config.serve_precompressed && path.is_file() {
+        true => Encoding::PRECOMPRESSED
+            .into_iter()
+            .filter(|encoding| {
+                Path::new(&format!("{absolute_path}.{}", encoding.file_extension())).is_file()
+            })
+            .collect(),
+        false => vec![],
+    };
 
-    if config.cache_files {
-        match &mut state.cached_files {
-                Some(ref mut cached_files) => {
-                    if let Some(cached_file) = cached_files.get(&urn) {
-                        return res
-                            .response_type(
-                                ResponseType::File(
-                                    FileResponse {
-                                        file_ext: cached_file.extension.as_str(),
-                                        file_content: cached_file.content.as_str(),
-                                    }
-                                )
-                            )
-                            .build()
-                    }
-                    else {
-                        let requested_file =
-                            fs::File::open(format!("{}/{}", config.absolute_static_content_path, &urn));
-
-                        let mut requested_file = match requested_file {
-                            Ok(file) => file,
-                            Err(_err) => return res.status_code(404).build()
-                        };
-
-                        let mut requested_content = String::new();
-
-                        if let Err(_) = requested_file.read_to_string(&mut requested_content) {
-                            return Err(ServerError::IOError(io::Error::new(
-                                io::ErrorKind::InvalidData,
-                                "File is not valid UTF-8 data.",
-                            )));
-                        }
+    let sidecar = accept_encoding
+        .and_then(|accept_encoding| negotiate(accept_encoding, &precompressed))
+        .map(|encoding| (encoding, format!("{urn}.{}", encoding.file_extension())));
+
+    let file = match load_file(
+        config,
+        state.file_cache.as_deref(),
+        sidecar.as_ref().map_or(urn.as_str(), |(_, sidecar_urn)| sidecar_urn),
+    )? {
+        Some(file) => file,
+        None => return res.status_code(StatusCode::NotFound).build(),
+    };
 
-                        cached_files.insert(urn.to_string(), CachedFile {
-                            extension: file_ext.to_string(),
-                            content: requested_content.clone(),
-                        });
-
-                        return res
-                            .response_type(
-                                ResponseType::File(
-                                    FileResponse {
-                                        file_ext,
-                                        file_content: requested_content.as_str(),
-                                    }
-                                )
-                            )
-                            .build()
+    // The response depends on Accept-Encoding whenever there's a compressed representation, even
+    // if the client ends up with the uncompressed file.
+    let mut vary_encoding = !precompressed.is_empty();
+
+    let (etag, modified, content, encoding) = match file {
+        LoadedFile::Memory(cached_file) => {
+            // Precompressed files are sent as they are, everything else may be compressed here.
+            let encoded = match sidecar {
+                Some(_) => None,
+                None => {
+                    let mime_type = config.mime_types.get(&file_ext);
+                    let size = cached_file.content.len();
+
+                    let compressible = config.compression
+                        && size >= config.compression_min_size
+                        && compression::is_compressible(&config.compression_mime_types, &mime_type);
+
+                    vary_encoding |= compressible;
+
+                    match accept_encoding {
+                        Some(accept_encoding) if compressible => {
+                            negotiate(accept_encoding, &Encoding::ALL)
+                                .and_then(|encoding| {
+                                    let encoded = match &state.file_cache {
+                                        Some(file_cache) if config.cache_files => {
+                                            file_cache.encode(&urn, &cached_file, encoding)
+                                        }
+                                        _ => cached_file.encode(encoding),
+                                    };
+
+                                    Some((encoding, encoded?))
+                                })
+                                // Not worth it if compression doesn't make the file smaller.
+                                .filter(|(_, encoded)| encoded.len() < size)
+                        }
+                        _ => None,
                     }
-                },
-                None => return Err(ServerError::from(io::Error::new(io::ErrorKind::Other, "State is a None value even though 'cache_files' configuration is set to true. This should never occur, this is probably a bug.")))
+                }
             };
-    }
 
-    let requested_file =
-        fs::File::open(format!("{}/{urn}", config.absolute_static_content_path));
+            // The content is shared with the file cache rather than copied into the response.
+            let (encoding, content) = match (&sidecar, encoded) {
+                (Some((encoding, _)), _) => (Some(*encoding), Arc::clone(&cached_file.content)),
+                (None, Some((encoding, encoded))) => (Some(encoding), encoded),
+                (None, None) => (None, Arc::clone(&cached_file.content)),
+            };
 
-    let mut requested_file = match requested_file {
-        Ok(file) => file,
-        Err(_err) => return res.status_code(404).build(),
+            (
+                cached_file.etag.clone(),
+                cached_file.modified,
+                FileContent::Bytes(content),
+                encoding,
+            )
+        }
+        // Files this large are never compressed on the fly.
+        LoadedFile::Stream(file) => (
+            file.etag,
+            file.modified,
+            FileContent::Stream {
+                file: file.file,
+                length: file.length,
+            },
+            sidecar.map(|(encoding, _)| encoding),
+        ),
     };
 
-    let mut requested_content = String::new();
+    // Every encoding is a different representation of the file with its own validator.
+    let etag = match encoding {
+        Some(encoding) => variant_etag(&etag, encoding.as_str()),
+        None => etag,
+    };
+
+    let mut res = res.header("ETag", etag.as_str());
+
+    if vary_encoding {
+        res = res.vary("Accept-Encoding");
+    }
+
+    if let Some(modified) = modified {
+        // A modification time in the future would make clients' caches misbehave.
+        let last_modified = modified.min(SystemTime::now());
+        res = res.header("Last-Modified", format_http_date(last_modified));
+    }
 
-    if requested_file.read_to_string(&mut requested_content).is_err() {
-        return Err(ServerError::IOError(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "File is not valid UTF-8 data.",
-        )));
+    if let Some(status) = evaluate_preconditions(request, &etag, modified) {
+        return res
+            .status_code(status)
+            .response_type(ResponseType::Empty)
+            .build();
     }
 
+    let total = content.len();
+
+    // Range requests only apply to GET, and only if the validator in `If-Range` (if any) still
+    // matches the file. Ranges refer to the encoded content when the response is compressed.
+    let ranges = match request.headers.get("Range") {
+        Some(range)
+            if request.method == Method::Get && if_range_matches(request, &etag, modified) =>
+        {
+            parse_range(range, total)
+        }
+        _ => RangeResult::Full,
+    };
+
+    let ranges = match ranges {
+        RangeResult::Full => vec![],
+        RangeResult::Partial(ranges) => ranges,
+        RangeResult::NotSatisfiable => {
+            return res
+                .header("Content-Range", format!("bytes */{total}"))
+                .status_code(StatusCode::RangeNotSatisfiable)
+                .response_type(ResponseType::Empty)
+                .build()
+        }
+    };
+
     res
         .response_type(ResponseType::File(FileResponse {
-            file_ext,
-            file_content: requested_content.as_str(),
+            file_ext: file_ext.as_str(),
+            content,
+            ranges,
+            encoding,
         }))
         .build()
 }
+
+/// Returns the first of the configured index files found in the directory at `dir`.
+fn find_index_file<'a>(config: &'a Configuration, dir: &str) -> Option<&'a str> {
+    config
+        .index_files
+        .iter()
+        .find(|index_file| Path::new(&format!("{dir}/{index_file}")).is_file())
+        .map(String::as_str)
+}
+
+/// The URL of `urn` with the query of `request`, to redirect the client to. It's built from the
+/// normalized path since the raw one could start with `//`, which clients take for a URL on another
+/// host.
+fn location(urn: &str, request: &Request) -> String {
+    match &request.query {
+        Some(query) => format!("/{}?{query}", percent_encode(urn)),
+        None => format!("/{}", percent_encode(urn)),
+    }
+}
+
+/// Whether a request for `clean_urn` is answered with the file at `urn`, so that clients can be
+/// redirected from one to the other.
+fn serves_file(config: &Configuration, clean_urn: &str, urn: &str) -> bool {
+    let clean_path = format!("{}/{clean_urn}", config.absolute_static_content_path);
+
+    if clean_urn.is_empty() || clean_urn.ends_with('/') {
+        find_index_file(config, &clean_path)
+            .is_some_and(|index_file| format!("{clean_urn}{index_file}") == urn)
+    } else {
+        !Path::new(&clean_path).exists()
+    }
+}
+
+/// Whether the SPA fallback covers `urn`: it has to start with one of the included prefixes (if
+/// there are any) and with none of the excluded ones.
+fn spa_fallback_applies(config: &Configuration, urn: &str) -> bool {
+    let path = format!("/{urn}");
+    let starts_with = |prefix: &String| path.starts_with(prefix.as_str());
+
+    (config.spa_include_prefixes.is_empty() || config.spa_include_prefixes.iter().any(starts_with))
+        && !config.spa_exclude_prefixes.iter().any(starts_with)
+}
+
+/// A requested file, either held in memory or streamed from the disk if it's too large for that.
+enum LoadedFile<T> {
+    Memory(T),
+    Stream(StreamedFile),
+}
+
+/// Returns the file at `urn` from the file cache, reading it into the cache on a miss. Files larger
+/// than `cache_max_file_size` are never read into memory.
+///
+/// Returns `None` if the file couldn't be opened.
+fn load_file(
+    config: &Configuration,
+    file_cache: Option<&FileCache>,
+    urn: &str,
+) -> Result<Option<LoadedFile<Arc<CachedFile>>>, ServerError> {
+    if !config.cache_files {
+        return Ok(match read_requested_file(config, urn)? {
+            Some(LoadedFile::Memory(file)) => Some(LoadedFile::Memory(Arc::new(file))),
+            Some(LoadedFile::Stream(file)) => Some(LoadedFile::Stream(file)),
+            None => None,
+        });
+    }
+
+    let file_cache = match file_cache {
+        Some(file_cache) => file_cache,
+        None => return Err(ServerError::from(io::Error::other("State is a None value even though 'cache_files' configuration is set to true. This should never occur, this is probably a bug."))),
+    };
+
+    let path = format!("{}/{urn}", config.absolute_static_content_path);
+
+    match file_cache.lookup(urn, Path::new(&path)) {
+        Lookup::Hit(file) => return Ok(Some(LoadedFile::Memory(file))),
+        Lookup::NotFound => return Ok(None),
+        Lookup::Miss => {}
+    }
+
+    Ok(match read_requested_file(config, urn)? {
+        Some(LoadedFile::Memory(file)) => Some(LoadedFile::Memory(file_cache.insert(urn, file))),
+        Some(LoadedFile::Stream(file)) => Some(LoadedFile::Stream(file)),
+        None => {
+            file_cache.insert_not_found(urn);
+            None
+        }
+    })
+}
+
+/// Reads the requested file from the static content directory byte-for-byte, or opens it for
+/// streaming if it's larger than `cache_max_file_size`.
+///
+/// Returns `None` if the file couldn't be opened so that the caller can respond with 404.
+fn read_requested_file(
+    config: &Configuration,
+    urn: &str,
+) -> Result<Option<LoadedFile<CachedFile>>, ServerError> {
+    let mut requested_file =
+        match fs::File::open(format!("{}/{urn}", config.absolute_static_content_path)) {
+            Ok(file) => file,
+            Err(_err) => return Ok(None),
+        };
+
+    let metadata = requested_file.metadata()?;
+    let modified = metadata.modified().ok();
+
+    if metadata.len() > config.cache_max_file_size {
+        return Ok(Some(LoadedFile::Stream(StreamedFile::new(
+            requested_file,
+            metadata.len(),
+            modified,
+        )?)));
+    }
+
+    let mut requested_content = Vec::new();
+
+    requested_file.read_to_end(&mut requested_content)?;
+
+    Ok(Some(LoadedFile::Memory(CachedFile {
+        extension: get_file_extension(urn),
+        etag: generate_etag(&requested_content, modified),
+        content: requested_content.into(),
+        modified,
+        encoded: Default::default(),
+    })))
+}
@@ -1,32 +1,129 @@
 use std::path::{Component, Path, PathBuf};
 
-/// Finds the Uniform Resource Name in the request's buffer.
+/// Finds the Uniform Resource Name for the request's path, relative to the static content
+/// directory. The path is percent-decoded and normalized, so `/docs/./my%20file.html` and
+/// `/docs//my%20file.html` both refer to `docs/my file.html`. A trailing slash is kept since it
+/// tells directories apart from files, the root directory is the empty string. The query
+/// component is not part of the path, it's found in `Request::query`.
 ///
 /// Returns `None` if an invalid path was found.
-pub fn find_urn(buffer: &String) -> Option<String> {
-    let mut uri = String::new();
+pub fn find_urn(path: &str) -> Option<String> {
+    let path = percent_decode(path.strip_prefix('/')?)?;
+    let uri = normalize(&path)?;
 
-    for (i, c) in buffer.chars().enumerate() {
-        // After the "GET" in the INFO header.
-        if c == ' ' && i != 3 {
-            break;
+    if !path_is_valid(Path::new(&uri)) {
+        return None;
+    }
+
+    Some(uri)
+}
+
+/// The file a clean URL such as `about` is served from, `about.html`. Directories don't have one.
+pub fn html_file_for(urn: &str) -> Option<String> {
+    if urn.is_empty() || urn.ends_with('/') {
+        return None;
+    }
+
+    Some(format!("{urn}.html"))
+}
+
+/// The clean URL of an HTML file, without the extension: `/about.html` becomes `/about`, and
+/// `/docs/index.html` becomes `/docs/` since index files are served for their directory. Works
+/// for request paths as well as URNs.
+///
+/// Returns `None` if the path isn't an HTML file.
+pub fn clean_url(path: &str) -> Option<&str> {
+    let stem = path.strip_suffix(".html")?;
+
+    if stem.is_empty() || stem.ends_with('/') {
+        return None;
+    }
+
+    match stem.strip_suffix("index") {
+        Some(dir) if dir.is_empty() || dir.ends_with('/') => Some(dir),
+        _ => Some(stem),
+    }
+}
+
+/// Decodes the `%XX` escapes in a path.
+///
+/// Returns `None` for malformed escapes, for encoded slashes (which would otherwise turn into path
+/// separators), for NUL bytes and if the decoded path isn't valid UTF-8.
+pub fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            if bytes[i] == 0 {
+                return None;
+            }
+
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        let hex = bytes.get(i + 1..i + 3)?;
+
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
         }
-        if i > 4 {
-            uri.push(c);
+
+        let byte = u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+
+        if byte == b'/' || byte == 0 {
+            return None;
         }
+
+        decoded.push(byte);
+        i += 3;
     }
 
-    let path = std::path::Path::new(&uri);
+    String::from_utf8(decoded).ok()
+}
 
-    if !path_is_valid(Path::new(&path)) {
-        return None;
+/// Encodes a decoded path so that it can be sent back to the client, e.g. in a `Location` header.
+/// Slashes are kept, everything which isn't allowed in a path segment is escaped.
+pub fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(byte as char),
+            b'-' | b'.' | b'_' | b'~' | b'/' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*'
+            | b'+' | b',' | b';' | b'=' | b':' | b'@' => encoded.push(byte as char),
+            byte => encoded.push_str(&format!("%{byte:02X}")),
+        }
     }
 
-    if uri.is_empty() {
-        Some("index.html".to_string())
-    } else {
-        Some(uri)
+    encoded
+}
+
+/// Removes empty and `.` segments from a relative path and resolves `..` segments.
+///
+/// Returns `None` if the path leaves the directory it's relative to.
+fn normalize(path: &str) -> Option<String> {
+    let mut segments = vec![];
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
     }
+
+    let mut normalized = segments.join("/");
+
+    if !normalized.is_empty() && path.ends_with('/') {
+        normalized.push('/');
+    }
+
+    Some(normalized)
 }
 
 /// Invalidates the path in the URN to prevent path traversal attacks.
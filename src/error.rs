@@ -1,3 +1,4 @@
+use crate::core::config_file::ConfigError;
 use std::{fmt, num::ParseIntError, str::Utf8Error, time::SystemTimeError};
 
 #[derive(Debug)]
@@ -6,12 +7,20 @@ pub enum ServerError {
     IOError(std::io::Error),
     ParseIntError(ParseIntError),
     TimeError(SystemTimeError),
+    ConfigError(ConfigError),
     UnknownError,
 }
 
 impl fmt::Display for ServerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", ServerError::UnknownError)
+        match self {
+            Self::ParseUtf8Error(e) => write!(f, "{e}"),
+            Self::IOError(e) => write!(f, "{e}"),
+            Self::ParseIntError(e) => write!(f, "{e}"),
+            Self::TimeError(e) => write!(f, "{e}"),
+            Self::ConfigError(e) => write!(f, "invalid configuration: {e}"),
+            Self::UnknownError => write!(f, "unknown error"),
+        }
     }
 }
 
@@ -40,3 +49,9 @@ impl From<SystemTimeError> for ServerError {
         Self::TimeError(e)
     }
 }
+
+impl From<ConfigError> for ServerError {
+    fn from(e: ConfigError) -> Self {
+        Self::ConfigError(e)
+    }
+}
@@ -1,21 +1,143 @@
+use crate::compression::Encoding;
 use crate::error::ServerError;
+use std::collections::HashMap;
 use std::fs::File;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io};
 
-#[derive(Clone)]
 pub struct CachedFile {
     pub extension: String,
-    pub content: String,
+    /// Shared with the responses the file is sent in, so that they don't need a copy of it.
+    pub content: Arc<[u8]>,
+    /// The last modification time of the file, if the platform supports it.
+    pub modified: Option<SystemTime>,
+    pub etag: String,
+    /// Compressed copies of the content, created the first time they are requested. Cached files
+    /// are shared between threads, so they are behind a lock.
+    pub encoded: RwLock<HashMap<Encoding, Arc<[u8]>>>,
 }
 
-pub fn get_file_extension(filename: &str) -> &str {
-    let mut last_dot_index = 0;
-    for (i, c) in filename.char_indices() {
-        if c == '.' {
-            last_dot_index = i;
+impl CachedFile {
+    /// Returns the content compressed with `encoding`, compressing it if it hasn't been yet.
+    pub fn encode(&self, encoding: Encoding) -> Option<Arc<[u8]>> {
+        if let Some(encoded) = self.encoded(encoding) {
+            return Some(encoded);
         }
+
+        // Compress without holding the lock, another thread may do the same meanwhile.
+        let encoded: Arc<[u8]> = encoding.encode(&self.content)?.into();
+
+        let mut variants = self.encoded.write().unwrap_or_else(PoisonError::into_inner);
+        Some(Arc::clone(variants.entry(encoding).or_insert(encoded)))
+    }
+
+    /// Returns the content compressed with `encoding` if it has been compressed already.
+    pub fn encoded(&self, encoding: Encoding) -> Option<Arc<[u8]>> {
+        let variants = self.encoded.read().unwrap_or_else(PoisonError::into_inner);
+        variants.get(&encoding).cloned()
+    }
+
+    /// The number of bytes taken up by the compressed copies of the content.
+    pub fn encoded_size(&self) -> usize {
+        let variants = self.encoded.read().unwrap_or_else(PoisonError::into_inner);
+        variants.values().map(|encoded| encoded.len()).sum()
+    }
+}
+
+/// A file which is too large to be held in memory. It's sent to the client straight from the disk.
+pub struct StreamedFile {
+    pub file: File,
+    pub length: u64,
+    pub modified: Option<SystemTime>,
+    pub etag: String,
+}
+
+impl StreamedFile {
+    pub fn new(mut file: File, length: u64, modified: Option<SystemTime>) -> io::Result<Self> {
+        let etag = match modified_etag(length, modified) {
+            Some(etag) => etag,
+            None => {
+                // Hashing means reading the whole file, but only happens on platforms which
+                // don't keep track of modification times.
+                let mut hasher = Fnv1a::default();
+                io::copy(&mut file, &mut hasher)?;
+                format!("\"{length:x}-{:016x}\"", hasher.0)
+            }
+        };
+
+        Ok(Self {
+            file,
+            length,
+            modified,
+            etag,
+        })
+    }
+}
+
+/// 64-bit FNV-1a hash
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl io::Write for Fnv1a {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 = buf.iter().fold(self.0, |hash, b| {
+            (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+        });
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Generates a strong entity tag for a file from its size and modification time, or from a hash
+/// of its content if the modification time isn't available.
+pub fn generate_etag(content: &[u8], modified: Option<SystemTime>) -> String {
+    let length = content.len() as u64;
+
+    modified_etag(length, modified).unwrap_or_else(|| {
+        let mut hasher = Fnv1a::default();
+        io::Write::write_all(&mut hasher, content).unwrap_or_default();
+        format!("\"{length:x}-{:016x}\"", hasher.0)
+    })
+}
+
+fn modified_etag(length: u64, modified: Option<SystemTime>) -> Option<String> {
+    let modified = modified?.duration_since(UNIX_EPOCH).ok()?;
+
+    Some(format!(
+        "\"{length:x}-{:x}{:08x}\"",
+        modified.as_secs(),
+        modified.subsec_nanos()
+    ))
+}
+
+/// Derives the entity tag of another representation of a file, such as a compressed one, from the
+/// entity tag of the file itself.
+pub fn variant_etag(etag: &str, variant: &str) -> String {
+    match etag.strip_suffix('"') {
+        Some(etag) => format!("{etag}-{variant}\""),
+        None => format!("{etag}-{variant}"),
+    }
+}
+
+/// Returns the lowercase extension of the last component of `filename`, or an empty string if it
+/// has none.
+pub fn get_file_extension(filename: &str) -> String {
+    let name = filename.rsplit('/').next().unwrap_or(filename);
+
+    match name.rfind('.') {
+        // Hidden files such as `.htaccess` have no extension.
+        Some(i) if i > 0 => name[i + 1..].to_ascii_lowercase(),
+        _ => String::new(),
     }
-    &filename[last_dot_index + 1..]
 }
 
 pub fn find_file(absolute_static_content_path: &str, filename: &str) -> Result<File, ServerError> {
@@ -36,12 +158,3 @@ pub fn find_file(absolute_static_content_path: &str, filename: &str) -> Result<F
 
     Ok(file)
 }
-
-pub fn find_mime_type(file_extension: &str) -> &str {
-    match file_extension {
-        "html" => "text/html",
-        "css" => "text/css",
-        "js" => "application/javascript",
-        _ => "text/plain",
-    }
-}
@@ -1,41 +1,67 @@
-use crate::status::StatusCode;
-use std::collections::HashMap;
-
-pub fn find_buf_headers<'a>(buf: &[u8]) -> Result<HashMap<String, String>, StatusCode> {
-    let buffer_c = match std::str::from_utf8(buf) {
-        Ok(buffer_c) => buffer_c,
-        Err(_) => return Err(400),
-    };
-
-    let mut headers: HashMap<String, String> = HashMap::new();
-
-    let mut curr_header_name = String::new();
-    let mut curr_header_value = String::new();
-
-    let mut found_colon = false;
-
-    for c in buffer_c.chars() {
-        if c == ':' {
-            found_colon = true;
-        } else if c == '\r' || c == '\n' {
-            if !curr_header_name.is_empty() && !curr_header_value.is_empty() {
-                headers.insert(curr_header_name, curr_header_value);
-            }
-            curr_header_name = String::new();
-            curr_header_value = String::new();
-            found_colon = false;
-        } else {
-            if !found_colon {
-                curr_header_name.push(c);
-            } else if found_colon && c != ' ' {
-                curr_header_value.push(c);
-            }
-        }
-    }
-
-    if headers.is_empty() {
-        return Err(400);
-    }
-
-    Ok(headers)
+/// Case-insensitive map of HTTP header fields which keeps every value of repeated fields in the
+/// order they were received.
+#[derive(Clone, Debug, Default)]
+pub struct Headers {
+    fields: Vec<(String, String)>,
+}
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the first value of the header field `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val.as_str())
+    }
+
+    /// Returns every value of the header field `name`.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.fields
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val.as_str())
+    }
+
+    /// Returns every comma separated element of the header field `name`, for headers such as
+    /// `Connection` or `Accept-Encoding` which may be split across multiple fields.
+    pub fn get_list<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.get_all(name)
+            .flat_map(|val| val.split(','))
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Adds a value to the header field `name` without removing the existing ones.
+    pub fn append(&mut self, name: impl Into<String>, val: impl Into<String>) {
+        self.fields.push((name.into(), val.into()));
+    }
+
+    /// Replaces every value of the header field `name` with `val`.
+    pub fn insert(&mut self, name: impl Into<String>, val: impl Into<String>) {
+        let name = name.into();
+        self.remove(&name);
+        self.fields.push((name, val.into()));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.fields.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_str()))
+    }
 }
@@ -1,15 +1,91 @@
+use std::fmt;
+use std::str::FromStr;
+
 #[allow(dead_code)]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HttpProtocolVersion {
+    OneDotZero,
     OneDotOne,
     Two,
 }
 
-impl<'a> Into<&'a str> for HttpProtocolVersion {
-    fn into(self) -> &'a str {
-        match self {
-            Self::OneDotOne => "HTTP/1.1",
+impl From<HttpProtocolVersion> for &str {
+    fn from(version: HttpProtocolVersion) -> Self {
+        match version {
+            HttpProtocolVersion::OneDotZero => "HTTP/1.0",
+            HttpProtocolVersion::OneDotOne => "HTTP/1.1",
             _ => "HTTP/2",
         }
     }
 }
+
+impl FromStr for HttpProtocolVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HTTP/1.0" => Ok(Self::OneDotZero),
+            "HTTP/1.1" => Ok(Self::OneDotOne),
+            "HTTP/2" => Ok(Self::Two),
+            _ => Err(format!(
+                "expected 'HTTP/1.0', 'HTTP/1.1' or 'HTTP/2', found '{s}'"
+            )),
+        }
+    }
+}
+
+/// The request methods defined by RFC 9110 and RFC 5789 (PATCH).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+            Self::Connect => "CONNECT",
+            Self::Options => "OPTIONS",
+            Self::Trace => "TRACE",
+            Self::Patch => "PATCH",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    /// Method names are case-sensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "GET" => Ok(Self::Get),
+            "HEAD" => Ok(Self::Head),
+            "POST" => Ok(Self::Post),
+            "PUT" => Ok(Self::Put),
+            "DELETE" => Ok(Self::Delete),
+            "CONNECT" => Ok(Self::Connect),
+            "OPTIONS" => Ok(Self::Options),
+            "TRACE" => Ok(Self::Trace),
+            "PATCH" => Ok(Self::Patch),
+            _ => Err(format!("unknown HTTP method '{s}'")),
+        }
+    }
+}
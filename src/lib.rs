@@ -1,10 +1,18 @@
+pub mod access_log;
+pub mod cache;
+pub mod cli;
 pub mod codegen_utils;
+pub mod compression;
 pub mod configuration;
 pub mod core;
 pub mod error;
 pub mod file;
 pub mod headers;
 pub mod http;
+pub mod logger;
+pub mod mime;
+pub mod range;
+pub mod request;
 pub mod response;
 pub mod state;
 pub mod status;
@@ -0,0 +1,369 @@
+//! Request logging. Log messages are written by a dedicated thread so that the threads serving
+//! requests only send them over a channel and never wait on the disk or on each other.
+//!
+//! Log files can be rotated by size and by day, and are reopened when they are moved or removed
+//! by an external tool such as logrotate, or when asked to with `Logger::reopen` or a trigger file.
+
+use crate::compression;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often the log file is checked for having been moved or removed, and the trigger file (if
+/// any) for having been created.
+const REOPEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+enum Message {
+    Log(String),
+    /// Asks the writer thread to flush everything logged so far and to answer once it's done.
+    Flush(Sender<()>),
+    /// Asks the writer thread to close the log file and to open the file at its path again.
+    Reopen,
+    /// Asks the writer thread to reopen the log file whenever a file is created at this path.
+    Watch(PathBuf),
+}
+
+/// Where the writer thread writes the messages to.
+trait LogOutput: Write {
+    /// Closes the output and opens it again. Only files can be reopened.
+    fn reopen(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write> LogOutput for BufWriter<W> {}
+
+/// When log files are rotated and what happens to the rotated files.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LogRotation {
+    /// Rotate once the file reaches this many bytes, 0 disables rotation by size.
+    pub max_size: u64,
+    /// Rotate when the first message of a new day (UTC) is logged.
+    pub daily: bool,
+    /// The number of rotated files to keep, 0 keeps all of them.
+    pub max_files: usize,
+    /// Compress rotated files with gzip.
+    pub compress: bool,
+}
+
+/// A handle to the log writer thread. Clones share the same writer, which stops once every handle
+/// has been dropped.
+#[derive(Clone)]
+pub struct Logger {
+    tx: Sender<Message>,
+}
+
+impl Logger {
+    /// Starts the writer thread, which appends every logged message to `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self::spawn(BufWriter::new(writer))
+    }
+
+    /// Logs to the file at `path`, creating it if it doesn't exist. Rotated files are kept next to
+    /// it, named after the file followed by the time of the rotation.
+    pub fn open(path: impl AsRef<Path>, rotation: LogRotation) -> io::Result<Self> {
+        Ok(Self::spawn(LogFile::open(path.as_ref(), rotation)?))
+    }
+
+    fn spawn(output: impl LogOutput + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || write_messages(rx, output));
+
+        Self { tx }
+    }
+
+    /// Queues `message` to be written to the log.
+    pub fn log(&self, message: impl Into<String>) {
+        // The writer thread only stops once every handle is gone, so this can't fail.
+        let _ = self.tx.send(Message::Log(message.into()));
+    }
+
+    /// Waits until every message logged so far has been written.
+    pub fn flush(&self) {
+        let (tx, rx) = mpsc::channel();
+
+        if self.tx.send(Message::Flush(tx)).is_ok() {
+            let _ = rx.recv();
+        }
+    }
+
+    /// Closes the log file and opens the file at its path again, for when an external tool such as
+    /// logrotate moved it. Moved and removed files are noticed by themselves within a second, this
+    /// is for when that isn't enough.
+    pub fn reopen(&self) {
+        let _ = self.tx.send(Message::Reopen);
+    }
+
+    /// Reopens the log file whenever a file is created at `trigger`, which is then removed. It's
+    /// checked every second, so that tools which can't call `reopen` can ask for it with `touch`.
+    pub fn watch(&self, trigger: impl Into<PathBuf>) {
+        let _ = self.tx.send(Message::Watch(trigger.into()));
+    }
+}
+
+fn write_messages(rx: Receiver<Message>, mut output: impl LogOutput) {
+    let mut trigger: Option<PathBuf> = None;
+    let mut last_check = Instant::now();
+
+    loop {
+        // Wake up regularly even if nothing is logged, to look for the trigger file.
+        let mut message = match rx.recv_timeout(REOPEN_CHECK_INTERVAL) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        if last_check.elapsed() >= REOPEN_CHECK_INTERVAL {
+            last_check = Instant::now();
+
+            if let Some(trigger) = &trigger {
+                if fs::remove_file(trigger).is_ok() {
+                    reopen(&mut output);
+                }
+            }
+        }
+
+        // Write everything which is already queued before flushing, so that a busy server doesn't
+        // flush on every request.
+        while let Some(next) = message {
+            match next {
+                Message::Log(line) => {
+                    if output.write_all(line.as_bytes()).is_err() {
+                        println!("Warning: something went wrong whilst writing to the logfile. Maybe it's too large?");
+                    }
+                }
+                Message::Flush(done) => {
+                    let _ = output.flush();
+                    let _ = done.send(());
+                }
+                Message::Reopen => reopen(&mut output),
+                Message::Watch(path) => trigger = Some(path),
+            }
+
+            message = rx.try_recv().ok();
+        }
+
+        let _ = output.flush();
+    }
+}
+
+fn reopen(output: &mut impl LogOutput) {
+    if let Err(e) = output.reopen() {
+        println!("Warning: failed reopening the logfile: {e}");
+    }
+}
+
+/// A log file which rotates itself. Every call to `write` is expected to be a whole message, so
+/// that messages are never split between two files.
+struct LogFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    file: BufWriter<File>,
+    /// The metadata of the open file, used to notice when the file is moved or removed.
+    metadata: Metadata,
+    size: u64,
+    /// The day (since the Unix epoch) of the messages in the file.
+    day: u64,
+    last_check: Instant,
+}
+
+impl LogFile {
+    fn open(path: &Path, rotation: LogRotation) -> io::Result<Self> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        let metadata = file.metadata()?;
+
+        // A file left over from a previous run belongs to the day it was last written to.
+        let day = match metadata.len() {
+            0 => today(),
+            _ => metadata.modified().map_or_else(|_| today(), day_of),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            rotation,
+            file: BufWriter::new(file),
+            size: metadata.len(),
+            metadata,
+            day,
+            last_check: Instant::now(),
+        })
+    }
+
+    fn needs_rotation(&self, len: usize) -> bool {
+        let too_large =
+            self.rotation.max_size > 0 && self.size + len as u64 > self.rotation.max_size;
+
+        // Empty files are never rotated.
+        self.size > 0 && (too_large || (self.rotation.daily && self.day != today()))
+    }
+
+    /// Whether the file at `path` is no longer the one being written to.
+    fn was_moved(&self) -> bool {
+        match fs::metadata(&self.path) {
+            Ok(metadata) => !same_file(&metadata, &self.metadata),
+            Err(_) => true,
+        }
+    }
+
+    /// Closes the file and opens the one which is at its path now.
+    fn reopen(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        *self = LogFile::open(&self.path, self.rotation)?;
+        Ok(())
+    }
+
+    /// Renames the current file, starts a new one and removes the oldest rotated files.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        let rotated = self.rotated_path();
+        fs::rename(&self.path, &rotated)?;
+        self.reopen()?;
+
+        if self.rotation.compress {
+            let gzipped = compression::gzip(&fs::read(&rotated)?);
+            let mut gzipped_path = rotated.clone().into_os_string();
+            gzipped_path.push(".gz");
+
+            fs::write(&gzipped_path, gzipped)?;
+            fs::remove_file(&rotated)?;
+        }
+
+        if self.rotation.max_files > 0 {
+            self.remove_old_files()?;
+        }
+
+        Ok(())
+    }
+
+    /// `access.log.784111777`, with a counter added if a file was already rotated this second.
+    fn rotated_path(&self) -> PathBuf {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let base = format!("{}.{secs}", self.path.display());
+
+        let taken =
+            |path: &str| Path::new(path).exists() || Path::new(&format!("{path}.gz")).exists();
+
+        let mut rotated = base.clone();
+        let mut counter = 0;
+
+        while taken(&rotated) {
+            counter += 1;
+            rotated = format!("{base}-{counter}");
+        }
+
+        PathBuf::from(rotated)
+    }
+
+    fn remove_old_files(&self) -> io::Result<()> {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return Ok(());
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+
+        let mut rotated = vec![];
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let modified = entry.metadata()?.modified()?;
+                rotated.push((modified, entry.file_name(), entry.path()));
+            }
+        }
+
+        // Oldest first, the names break ties between files rotated within the same second.
+        rotated.sort();
+
+        let excess = rotated.len().saturating_sub(self.rotation.max_files);
+
+        for (_, _, path) in rotated.into_iter().take(excess) {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LogOutput for LogFile {
+    fn reopen(&mut self) -> io::Result<()> {
+        LogFile::reopen(self)
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.last_check.elapsed() >= REOPEN_CHECK_INTERVAL {
+            self.last_check = Instant::now();
+
+            if self.was_moved() {
+                self.reopen()?;
+            }
+        }
+
+        if self.size == 0 {
+            self.day = today();
+        }
+
+        // Tools such as logrotate with `copytruncate` empty the file without moving it, which
+        // mustn't be mistaken for a full file.
+        if self.needs_rotation(buf.len()) {
+            self.file.flush()?;
+            self.size = self.size.min(self.file.get_ref().metadata()?.len());
+
+            if self.size == 0 {
+                self.day = today();
+            }
+        }
+
+        if self.needs_rotation(buf.len()) {
+            if let Err(e) = self.rotate() {
+                println!("Warning: failed rotating the logfile: {e}");
+
+                // Keep logging to whatever file is at the path now, and only try again once the
+                // file grows by another `max_size` bytes or on the next day.
+                if self.was_moved() {
+                    self.reopen()?;
+                }
+                self.size = 0;
+                self.day = today();
+            }
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86400)
+}
+
+fn today() -> u64 {
+    day_of(SystemTime::now())
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Without inode numbers only removed files are noticed.
+#[cfg(not(unix))]
+fn same_file(_: &Metadata, _: &Metadata) -> bool {
+    true
+}
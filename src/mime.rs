@@ -0,0 +1,213 @@
+use crate::core::config_file::ConfigError;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The type sent for files with an unknown extension.
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// Built-in extension table, extensions must be lowercase.
+const BUILTIN_MIME_TYPES: [(&str, &str); 84] = [
+    // Text
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("csv", "text/csv"),
+    ("md", "text/markdown"),
+    ("ics", "text/calendar"),
+    ("vtt", "text/vtt"),
+    // Structured data
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("webmanifest", "application/manifest+json"),
+    ("xml", "application/xml"),
+    ("xhtml", "application/xhtml+xml"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("wasm", "application/wasm"),
+    // Images
+    ("png", "image/png"),
+    ("apng", "image/apng"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("jxl", "image/jxl"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    // Fonts
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("eot", "application/vnd.ms-fontobject"),
+    // Audio
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/opus"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+    ("weba", "audio/webm"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+    // Video
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ogv", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mkv", "video/x-matroska"),
+    ("mpeg", "video/mpeg"),
+    ("mpg", "video/mpeg"),
+    ("m3u8", "application/vnd.apple.mpegurl"),
+    // Documents
+    ("pdf", "application/pdf"),
+    ("rtf", "application/rtf"),
+    ("epub", "application/epub+zip"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    // Archives
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tgz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("bz2", "application/x-bzip2"),
+    ("xz", "application/x-xz"),
+    ("7z", "application/x-7z-compressed"),
+    ("zst", "application/zstd"),
+    // Other
+    ("bin", "application/octet-stream"),
+    ("exe", "application/octet-stream"),
+    ("dmg", "application/x-apple-diskimage"),
+    ("apk", "application/vnd.android.package-archive"),
+    ("swf", "application/x-shockwave-flash"),
+    ("jar", "application/java-archive"),
+];
+
+/// Types which are sent with `charset=utf-8` on top of every `text/*` type.
+const UTF8_MIME_TYPES: [&str; 7] = [
+    "application/json",
+    "application/ld+json",
+    "application/manifest+json",
+    "application/xml",
+    "application/xhtml+xml",
+    "application/rss+xml",
+    "image/svg+xml",
+];
+
+/// Maps file extensions to MIME types using the built-in table and optional user overrides.
+#[derive(Clone, Debug, Default)]
+pub struct MimeTypes {
+    overrides: HashMap<String, String>,
+}
+
+impl MimeTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads overrides from a file in the `mime.types` format used by Apache and nginx.
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path)?;
+        Self::read_from_str(&source)
+    }
+
+    /// Parses overrides in the `mime.types` format: one MIME type per line followed by the
+    /// extensions which should use it, e.g. `text/x-rust rs`. Comments start with `#`.
+    pub fn read_from_str(source: &str) -> Result<Self, ConfigError> {
+        let mut mime_types = Self::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            // nginx wraps the list in `types { ... }` and ends every line with a semicolon.
+            if line.starts_with("types") || line == "}" {
+                continue;
+            }
+
+            let mut parts = line.trim_end_matches(';').split_whitespace();
+
+            let mime_type = match parts.next() {
+                Some(mime_type) if mime_type.contains('/') => mime_type,
+                _ => {
+                    return Err(ConfigError::Parse {
+                        line: i + 1,
+                        message: format!("expected 'type/subtype extension...', found '{line}'"),
+                    })
+                }
+            };
+
+            for extension in parts {
+                mime_types.insert(extension, mime_type);
+            }
+        }
+
+        Ok(mime_types)
+    }
+
+    /// Overrides the MIME type of an extension.
+    pub fn insert(&mut self, extension: &str, mime_type: &str) {
+        self.overrides.insert(
+            extension.trim_start_matches('.').to_ascii_lowercase(),
+            mime_type.to_string(),
+        );
+    }
+
+    /// Returns the `Content-Type` for a file extension, including the charset for text types.
+    pub fn get(&self, extension: &str) -> String {
+        let extension = extension.to_ascii_lowercase();
+
+        let mime_type = match self.overrides.get(&extension) {
+            Some(mime_type) => mime_type.as_str(),
+            None => find_mime_type(&extension),
+        };
+
+        if mime_type.contains("charset=") {
+            mime_type.to_string()
+        } else if mime_type.starts_with("text/") || UTF8_MIME_TYPES.contains(&mime_type) {
+            format!("{mime_type}; charset=utf-8")
+        } else {
+            mime_type.to_string()
+        }
+    }
+}
+
+/// Looks up the built-in MIME type of a lowercase file extension.
+pub fn find_mime_type(file_extension: &str) -> &'static str {
+    BUILTIN_MIME_TYPES
+        .iter()
+        .find(|(extension, _)| *extension == file_extension)
+        .map(|(_, mime_type)| *mime_type)
+        .unwrap_or(DEFAULT_MIME_TYPE)
+}
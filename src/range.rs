@@ -0,0 +1,107 @@
+use crate::response::body::BodyPart;
+
+/// Maximum number of ranges served in a single `multipart/byteranges` response. Requests asking
+/// for more are answered with the full content instead.
+const MAX_RANGES: usize = 32;
+
+/// An inclusive byte range of a file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// The value of the `Content-Range` header for this range of a file of `total` bytes.
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{total}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RangeResult {
+    /// The header is absent, invalid or should be ignored, the full content must be sent.
+    Full,
+    Partial(Vec<ByteRange>),
+    NotSatisfiable,
+}
+
+/// Parses the value of a `Range` header for content of `len` bytes.
+pub fn parse_range(header: &str, len: u64) -> RangeResult {
+    let specs = match header.split_once('=') {
+        Some((unit, specs)) if unit.trim().eq_ignore_ascii_case("bytes") => specs,
+        _ => return RangeResult::Full,
+    };
+
+    let mut ranges = vec![];
+
+    for spec in specs.split(',').map(|spec| spec.trim()) {
+        let (first, last) = match spec.split_once('-') {
+            Some(bounds) => bounds,
+            None => return RangeResult::Full,
+        };
+
+        let range = match (first.parse::<u64>(), last.parse::<u64>()) {
+            // "500-999"
+            (Ok(first), Ok(last)) if first <= last => {
+                (first < len).then(|| ByteRange {
+                    start: first,
+                    end: last.min(len - 1),
+                })
+            }
+            // "500-"
+            (Ok(first), Err(_)) if last.is_empty() => (first < len).then(|| ByteRange {
+                start: first,
+                end: len - 1,
+            }),
+            // "-500", the last 500 bytes.
+            (Err(_), Ok(suffix)) if first.is_empty() => (suffix > 0 && len > 0).then(|| ByteRange {
+                start: len.saturating_sub(suffix),
+                end: len - 1,
+            }),
+            _ => return RangeResult::Full,
+        };
+
+        if let Some(range) = range {
+            ranges.push(range);
+        }
+    }
+
+    if ranges.len() > MAX_RANGES {
+        return RangeResult::Full;
+    }
+
+    if ranges.is_empty() {
+        return RangeResult::NotSatisfiable;
+    }
+
+    RangeResult::Partial(ranges)
+}
+
+/// Builds the parts of a `multipart/byteranges` body for content of `total` bytes.
+pub fn multipart_parts(
+    ranges: &[ByteRange],
+    total: u64,
+    content_type: &str,
+    boundary: &str,
+) -> Vec<BodyPart> {
+    let mut parts = vec![];
+
+    for (i, range) in ranges.iter().enumerate() {
+        // Every part but the first one starts on a new line.
+        let separator = if i == 0 { "" } else { "\r\n" };
+
+        parts.push(BodyPart::Bytes(
+            format!(
+                "{separator}--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: {}\r\n\r\n",
+                range.content_range(total)
+            )
+            .into_bytes(),
+        ));
+        parts.push(BodyPart::Range(*range));
+    }
+
+    parts.push(BodyPart::Bytes(format!("\r\n--{boundary}--\r\n").into_bytes()));
+
+    parts
+}
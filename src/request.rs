@@ -0,0 +1,304 @@
+use crate::headers::Headers;
+use crate::http::{HttpProtocolVersion, Method};
+use crate::status::StatusCode;
+use std::io::{BufRead, Read};
+
+/// Maximum size of the request line and headers combined.
+const MAX_HEAD_SIZE: u64 = 64 * 1024;
+
+/// Maximum size of a request body. A static file server has no use for large bodies.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// A parsed HTTP/1.x request.
+#[derive(Debug)]
+pub struct Request {
+    pub method: Method,
+    /// The request target exactly as it was sent by the client.
+    pub target: String,
+    /// The path component of the target, always starting with `/` (or `*` for `OPTIONS *`).
+    pub path: String,
+    /// The query component of the target without the leading `?`.
+    pub query: Option<String>,
+    pub version: HttpProtocolVersion,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Reads and parses a single request from `input`, including its body.
+    ///
+    /// Returns the status code the server should respond with if the request is malformed.
+    pub fn read_from(mut input: impl BufRead) -> Result<Self, StatusCode> {
+        let head = read_head(&mut input)?;
+        let head = std::str::from_utf8(&head).map_err(|_| StatusCode::BadRequest)?;
+
+        let mut lines = head
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line));
+
+        let (method, target, version) = match lines.next() {
+            Some(request_line) => parse_request_line(request_line)?,
+            None => return Err(StatusCode::BadRequest),
+        };
+
+        let mut headers = Headers::new();
+
+        for line in lines.filter(|line| !line.is_empty()) {
+            let (name, val) = parse_header_field(line)?;
+            headers.append(name, val);
+        }
+
+        // Every HTTP/1.1 client has to send at least the Host header, HTTP/1.0 requests may come
+        // without any. The value of Host isn't used, so only requests without a single header are
+        // rejected.
+        if version == HttpProtocolVersion::OneDotOne && headers.is_empty() {
+            return Err(StatusCode::BadRequest);
+        }
+
+        let (path, query) = split_target(target)?;
+
+        if path == "*" && method != Method::Options {
+            return Err(StatusCode::BadRequest);
+        }
+
+        let body = read_body(&mut input, &headers)?;
+
+        Ok(Self {
+            method,
+            target: target.to_string(),
+            path,
+            query,
+            version,
+            headers,
+            body,
+        })
+    }
+
+    /// Whether the client wants the connection to stay open according to its `Connection` header,
+    /// falling back to the default of its HTTP version (persistent since HTTP/1.1).
+    pub fn keep_alive(&self) -> bool {
+        let mut keep_alive = self.version != HttpProtocolVersion::OneDotZero;
+
+        for option in self.headers.get_list("Connection") {
+            if option.eq_ignore_ascii_case("close") {
+                return false;
+            }
+            if option.eq_ignore_ascii_case("keep-alive") {
+                keep_alive = true;
+            }
+        }
+
+        keep_alive
+    }
+}
+
+/// Reads the request line and the headers, up to and including the empty line which ends them.
+fn read_head(input: &mut impl BufRead) -> Result<Vec<u8>, StatusCode> {
+    let mut buf = vec![];
+
+    loop {
+        let remaining = MAX_HEAD_SIZE.saturating_sub(buf.len() as u64);
+        if remaining == 0 {
+            return Err(StatusCode::RequestHeaderFieldsTooLarge);
+        }
+
+        let count = match input.take(remaining).read_until(b'\n', &mut buf) {
+            Ok(count) => count,
+            Err(_) => return Err(StatusCode::BadRequest),
+        };
+
+        // The client stopped sending data before the end of the headers.
+        if count == 0 {
+            return Err(StatusCode::BadRequest);
+        }
+
+        let line = &buf[buf.len() - count..];
+
+        if !line.ends_with(b"\n") {
+            // Either the client stopped sending data in the middle of a line or the limit was
+            // reached before the end of the line.
+            if (count as u64) < remaining {
+                return Err(StatusCode::BadRequest);
+            }
+            if buf.len() == count {
+                return Err(StatusCode::UriTooLong);
+            }
+            return Err(StatusCode::RequestHeaderFieldsTooLarge);
+        }
+
+        if line == b"\n" || line == b"\r\n" {
+            // Empty lines before the request line are allowed and ignored.
+            if buf.len() == count {
+                buf.clear();
+                continue;
+            }
+            return Ok(buf);
+        }
+    }
+}
+
+fn parse_request_line(line: &str) -> Result<(Method, &str, HttpProtocolVersion), StatusCode> {
+    let mut parts = line.split(' ');
+
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next(), parts.next())
+    {
+        (Some(method), Some(target), Some(version), None) => (method, target, version),
+        _ => return Err(StatusCode::BadRequest),
+    };
+
+    if method.is_empty() || !method.bytes().all(is_token_char) {
+        return Err(StatusCode::BadRequest);
+    }
+
+    let method = method
+        .parse()
+        .map_err(|_| StatusCode::MethodNotAllowed)?;
+
+    if target.is_empty() || !target.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(StatusCode::BadRequest);
+    }
+
+    let version = match version {
+        "HTTP/1.0" => HttpProtocolVersion::OneDotZero,
+        "HTTP/1.1" => HttpProtocolVersion::OneDotOne,
+        _ => match version.strip_prefix("HTTP/").map(|number| number.as_bytes()) {
+            Some([major, b'.', minor]) if major.is_ascii_digit() && minor.is_ascii_digit() => {
+                return Err(StatusCode::HttpVersionNotSupported)
+            }
+            _ => return Err(StatusCode::BadRequest),
+        },
+    };
+
+    Ok((method, target, version))
+}
+
+fn parse_header_field(line: &str) -> Result<(&str, &str), StatusCode> {
+    let (name, val) = match line.split_once(':') {
+        Some(field) => field,
+        None => return Err(StatusCode::BadRequest),
+    };
+
+    // Rejects obsolete line folding and whitespace between the name and the colon as well.
+    if name.is_empty() || !name.bytes().all(is_token_char) {
+        return Err(StatusCode::BadRequest);
+    }
+
+    Ok((name, val.trim_matches(|c| c == ' ' || c == '\t')))
+}
+
+/// Splits the request target into its path and query. Absolute-form targets
+/// (`http://host/path`) are reduced to their path.
+fn split_target(target: &str) -> Result<(String, Option<String>), StatusCode> {
+    let target = match target.split_once("://") {
+        Some((scheme, rest))
+            if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") =>
+        {
+            match rest.find(['/', '?']) {
+                Some(i) if rest.as_bytes()[i] == b'/' => &rest[i..],
+                Some(i) => return Ok(("/".to_string(), Some(rest[i + 1..].to_string()))),
+                None => "/",
+            }
+        }
+        _ => target,
+    };
+
+    if target != "*" && !target.starts_with('/') {
+        return Err(StatusCode::BadRequest);
+    }
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (target, None),
+    };
+
+    Ok((path.to_string(), query))
+}
+
+fn read_body(input: &mut impl BufRead, headers: &Headers) -> Result<Vec<u8>, StatusCode> {
+    let mut body = vec![];
+
+    if let Some(encoding) = headers.get_list("Transfer-Encoding").last() {
+        if headers.contains("Content-Length") {
+            return Err(StatusCode::BadRequest);
+        }
+        if !encoding.eq_ignore_ascii_case("chunked") {
+            return Err(StatusCode::NotImplemented);
+        }
+        return read_chunked_body(input);
+    }
+
+    let mut lengths = headers.get_list("Content-Length");
+
+    let length: u64 = match lengths.next() {
+        Some(length) if length.bytes().all(|b| b.is_ascii_digit()) => {
+            length.parse().map_err(|_| StatusCode::BadRequest)?
+        }
+        Some(_) => return Err(StatusCode::BadRequest),
+        None => return Ok(body),
+    };
+
+    // Repeated Content-Length fields are only allowed if they all agree.
+    if lengths.any(|other| other != length.to_string()) {
+        return Err(StatusCode::BadRequest);
+    }
+
+    if length > MAX_BODY_SIZE {
+        return Err(StatusCode::ContentTooLarge);
+    }
+
+    match input.take(length).read_to_end(&mut body) {
+        Ok(count) if count as u64 == length => Ok(body),
+        _ => Err(StatusCode::BadRequest),
+    }
+}
+
+fn read_chunked_body(input: &mut impl BufRead) -> Result<Vec<u8>, StatusCode> {
+    let mut body = vec![];
+
+    loop {
+        let mut line = String::new();
+        if input.take(1024).read_line(&mut line).is_err() || !line.ends_with('\n') {
+            return Err(StatusCode::BadRequest);
+        }
+
+        // Chunk extensions are ignored.
+        let size = line.trim_end().split(';').next().unwrap_or_default().trim();
+        let size = u64::from_str_radix(size, 16).map_err(|_| StatusCode::BadRequest)?;
+
+        if size == 0 {
+            break;
+        }
+
+        // A huge chunk size must not wrap around the limit.
+        match (body.len() as u64).checked_add(size) {
+            Some(total) if total <= MAX_BODY_SIZE => {}
+            _ => return Err(StatusCode::ContentTooLarge),
+        }
+
+        match input.take(size).read_to_end(&mut body) {
+            Ok(count) if count as u64 == size => {}
+            _ => return Err(StatusCode::BadRequest),
+        }
+
+        let mut crlf = String::new();
+        match input.take(2).read_line(&mut crlf) {
+            Ok(_) if crlf == "\r\n" || crlf == "\n" => {}
+            _ => return Err(StatusCode::BadRequest),
+        }
+    }
+
+    // Trailer fields are read and ignored until the final empty line.
+    loop {
+        let mut line = String::new();
+        match input.take(MAX_HEAD_SIZE).read_line(&mut line) {
+            Ok(_) if line == "\r\n" || line == "\n" => return Ok(body),
+            Ok(count) if count > 0 && line.ends_with('\n') => {}
+            _ => return Err(StatusCode::BadRequest),
+        }
+    }
+}
+
+/// Characters allowed in tokens such as method and header names (RFC 9110 section 5.6.2).
+fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
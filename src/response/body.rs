@@ -0,0 +1,110 @@
+use crate::range::ByteRange;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+/// The body of a response, either held in memory or streamed from a file.
+pub enum Body {
+    Bytes(Vec<u8>),
+    /// Parts of content which is shared with the file cache, so that it isn't copied for every
+    /// response.
+    Shared {
+        content: Arc<[u8]>,
+        parts: Vec<BodyPart>,
+    },
+    /// Parts of a file which are copied to the client in chunks instead of being read into
+    /// memory, possibly interleaved with in-memory parts such as the headers of a multipart body.
+    File { file: File, parts: Vec<BodyPart> },
+}
+
+pub enum BodyPart {
+    Bytes(Vec<u8>),
+    Range(ByteRange),
+}
+
+impl BodyPart {
+    pub fn len(&self) -> u64 {
+        match self {
+            BodyPart::Bytes(bytes) => bytes.len() as u64,
+            BodyPart::Range(range) => range.end - range.start + 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Body {
+    pub fn empty() -> Self {
+        Body::Bytes(vec![])
+    }
+
+    /// The slices an in-memory body consists of, in order, or `None` if it's streamed from a file.
+    pub fn slices(&self) -> Option<Vec<&[u8]>> {
+        match self {
+            Body::Bytes(bytes) => Some(vec![bytes]),
+            Body::Shared { content, parts } => Some(
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        BodyPart::Bytes(bytes) => bytes.as_slice(),
+                        BodyPart::Range(range) => {
+                            &content[range.start as usize..=range.end as usize]
+                        }
+                    })
+                    .collect(),
+            ),
+            Body::File { .. } => None,
+        }
+    }
+
+    /// The number of bytes the body consists of, which is sent as the `Content-Length`.
+    pub fn len(&self) -> u64 {
+        match self {
+            Body::Bytes(bytes) => bytes.len() as u64,
+            Body::Shared { parts, .. } | Body::File { parts, .. } => {
+                parts.iter().map(BodyPart::len).sum()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the body to `writer`. File ranges are copied with `io::copy`, which uses zero-copy
+    /// system calls such as `sendfile` when writing a file to a socket on Linux.
+    pub fn write_to<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Body::Bytes(_) | Body::Shared { .. } => {
+                for slice in self.slices().unwrap_or_default() {
+                    writer.write_all(slice)?;
+                }
+
+                Ok(())
+            }
+            Body::File { file, parts } => {
+                for part in parts {
+                    match part {
+                        BodyPart::Bytes(bytes) => writer.write_all(bytes)?,
+                        BodyPart::Range(range) => {
+                            let length = range.end - range.start + 1;
+                            file.seek(SeekFrom::Start(range.start))?;
+
+                            let copied = io::copy(&mut (&*file).take(length), writer)?;
+
+                            // The file was truncated while it was being sent, the client can't be
+                            // given the promised number of bytes anymore.
+                            if copied < length {
+                                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
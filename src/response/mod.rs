@@ -1,12 +1,84 @@
+pub mod body;
 pub mod response_builder;
 pub mod types;
 pub mod utils;
 
 use crate::status::StatusCode;
+use body::Body;
 use std::collections::HashMap;
 use std::fs::File;
+use std::io::{self, IoSlice, Write};
 
 pub type ErrorResponse = (HashMap<String, String>, StatusCode);
 pub type OkResponse = (HashMap<String, String>, Option<String>, Option<File>);
 
 pub type ServerResponse = Result<OkResponse, ErrorResponse>;
+
+/// A finished HTTP response, ready to be written to the client.
+pub struct Response {
+    pub status_code: StatusCode,
+    /// The status line and the headers.
+    pub head: Vec<u8>,
+    pub body: Body,
+    /// Whether the connection should be kept open for further requests once the response has been
+    /// sent.
+    pub keep_alive: bool,
+}
+
+impl Response {
+    /// Writes the whole response to `writer`.
+    ///
+    /// In-memory bodies are sent together with the head in a single vectored write, without
+    /// copying them, so that a small response isn't split into two segments which Nagle's algorithm
+    /// would hold back until the client acknowledges the first one.
+    pub fn write_to<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        match self.body.slices() {
+            Some(slices) => {
+                let mut slices: Vec<IoSlice> = std::iter::once(self.head.as_slice())
+                    .chain(slices)
+                    .map(IoSlice::new)
+                    .collect();
+                write_all_vectored(writer, &mut slices)?;
+            }
+            None => {
+                writer.write_all(&self.head)?;
+                self.body.write_to(writer)?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Returns the whole response as bytes, reading streamed bodies into memory.
+    pub fn into_bytes(mut self) -> io::Result<Vec<u8>> {
+        let mut data = vec![];
+        self.write_to(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Writes all of `slices` to `writer`, with as few calls to `write_vectored` as it allows.
+fn write_all_vectored<W: Write>(writer: &mut W, mut slices: &mut [IoSlice]) -> io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+
+    while !slices.is_empty() {
+        match writer.write_vectored(slices) {
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+            Ok(written) => IoSlice::advance_slices(&mut slices, written),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Status codes which the server commonly responds with a fallback page for. `--check-config`
+/// expects a `<code>.html` page in the static content directory for each of them, any other status
+/// code falls back to a generated page when it has none.
+pub const FALLBACK_PAGES: [StatusCode; 4] = [
+    StatusCode::BadRequest,
+    StatusCode::NotFound,
+    StatusCode::MethodNotAllowed,
+    StatusCode::InternalServerError,
+];
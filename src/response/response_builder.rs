@@ -1,23 +1,32 @@
-use super::types::ResponseType;
+use super::body::{Body, BodyPart};
+use super::types::{FileContent, ResponseType};
+use super::Response;
 use super::utils::*;
 use crate::core::configuration::Configuration;
 use crate::error::ServerError;
-use crate::file::*;
+use crate::headers::Headers;
+use crate::http::Method;
+use crate::range::{multipart_parts, ByteRange};
 use crate::status::StatusCode;
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// The "finalizer" struct for responses. Takes all the response data and turns them into a valid HTTP
 /// response string.
 pub struct ResponseBuilder<'a> {
     pub status_code: StatusCode,
-    pub req_headers: Option<HashMap<String, String>>,
-    pub config: &'a Configuration<'a>,
+    /// Headers set by the server for this particular response, such as the CORS headers.
+    pub headers: Headers,
+    pub config: &'a Configuration,
     // The field is wrapped inside Option because when the serve_request function initially calls
     // the builder it doesn't know about its required fields yet.
     pub response_type: Option<ResponseType<'a>>,
+    pub keep_alive: bool,
+    /// The method of the request. Responses to HEAD requests carry the same headers as the
+    /// equivalent GET response but no body.
+    pub method: Method,
 }
 
 impl<'a> ResponseBuilder<'a> {
@@ -29,11 +38,8 @@ impl<'a> ResponseBuilder<'a> {
         }
     }
 
-    pub fn req_headers(self, req_headers: HashMap<String, String>) -> Self {
-        Self {
-            req_headers: Some(req_headers),
-            ..self
-        }
+    pub fn headers(self, headers: Headers) -> Self {
+        Self { headers, ..self }
     }
 
     pub fn response_type(self, response_type: ResponseType<'a>) -> Self {
@@ -43,7 +49,28 @@ impl<'a> ResponseBuilder<'a> {
         }
     }
 
-    pub fn build(self) -> Result<String, ServerError> {
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &str, val: impl Into<String>) -> Self {
+        self.headers.insert(name, val);
+        self
+    }
+
+    /// Adds a request header field the response depends on to `Vary`, so that caches don't serve
+    /// it for requests with a different value.
+    pub fn vary(mut self, field: &str) -> Self {
+        self.headers.append("Vary", field);
+        self
+    }
+
+    pub fn method(self, method: Method) -> Self {
+        Self { method, ..self }
+    }
+
+    pub fn keep_alive(self, keep_alive: bool) -> Self {
+        Self { keep_alive, ..self }
+    }
+
+    pub fn build(self) -> Result<Response, ServerError> {
         let response_type = match self.response_type {
             Some(response_type) => response_type,
             None => return Err(ServerError::from(io::Error::new(io::ErrorKind::InvalidInput, "Builder function was ran before giving a valid response_type."))),
@@ -51,26 +78,26 @@ impl<'a> ResponseBuilder<'a> {
 
         let mut head: Vec<String> = vec![];
         let mut body: Vec<String> = vec![];
+        // Raw bytes which are sent as-is after the headers. Only directory listings are wrapped
+        // inside an HTML document, everything else is sent exactly as it was found on disk.
+        let content: Body;
         let protocol: &str = self.config.http_protocol_version.into();
         let status_code;
 
-        let mut headers = match self.req_headers {
-            Some(req_headers) => req_headers,
-            None => HashMap::new(),
-        };
-        
+        let mut headers = self.headers;
+
         if self.config.use_security_headers {
             /* Prevent malicious HTML */
-            headers.insert("X-Content-Type-Options".to_string(), "nosniff".to_string());
+            headers.insert("X-Content-Type-Options", "nosniff");
 
             /* Prevent clickjacking */
             if !self.config.allow_iframes {
-                headers.insert("X-Frame-Options".to_string(), "DENY".to_string());
+                headers.insert("X-Frame-Options", "DENY");
             }
 
             /* Prevent embedding resources from another origin */
             headers.insert(
-                "Cross-Origin-Resource-Policy".to_string(),
+                "Cross-Origin-Resource-Policy",
                 "same-origin".to_string(),
             );
         }
@@ -81,13 +108,20 @@ impl<'a> ResponseBuilder<'a> {
             }
         }
 
-        // Apply CORS headers
-        if headers.get("Access-Control-Allow-Origin").is_none() {
+        if self.keep_alive {
+            headers.insert("Connection", "keep-alive");
             headers.insert(
-                "Access-Control-Allow-Origin".into(),
-                "null".into(),
+                "Keep-Alive",
+                format!("timeout={}", self.config.keep_alive_timeout),
             );
-        };
+        } else {
+            headers.insert("Connection", "close");
+        }
+
+        // Apply CORS headers
+        if !headers.contains("Access-Control-Allow-Origin") {
+            headers.insert("Access-Control-Allow-Origin", "null");
+        }
 
         match response_type {
             ResponseType::File(res_data) => {
@@ -96,21 +130,60 @@ impl<'a> ResponseBuilder<'a> {
                         .duration_since(UNIX_EPOCH)?
                         .as_secs()
                         .to_string();
-                    headers.insert("Time".to_string(), curr_time);
+                    headers.insert("Time", curr_time);
                 }
 
+                let mime_type = self.config.mime_types.get(res_data.file_ext);
+                let total = res_data.content.len();
+
                 // Apply necessary headers and security headers
-                headers.insert(
-                    "Content-Type".into(),
-                    find_mime_type(res_data.file_ext).to_string(),
-                );
-                headers.insert(
-                    "Content-Length".into(),
-                    res_data.file_content.len().to_string(),
-                );
-
-                body.push(res_data.file_content.into());
-                status_code = self.status_code;
+                headers.insert("Accept-Ranges", "bytes");
+
+                if let Some(encoding) = res_data.encoding {
+                    headers.insert("Content-Encoding", encoding.as_str());
+                }
+
+                let parts = match res_data.ranges.as_slice() {
+                    [] => {
+                        headers.insert("Content-Type", mime_type.as_str());
+                        status_code = self.status_code;
+
+                        match total {
+                            0 => vec![],
+                            _ => vec![BodyPart::Range(ByteRange {
+                                start: 0,
+                                end: total - 1,
+                            })],
+                        }
+                    }
+                    [range] => {
+                        headers.insert("Content-Type", mime_type.as_str());
+                        headers.insert("Content-Range", range.content_range(total));
+                        status_code = StatusCode::PartialContent;
+
+                        vec![BodyPart::Range(*range)]
+                    }
+                    ranges => {
+                        let boundary = format!(
+                            "vrs-{:x}",
+                            SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos()
+                        );
+                        headers.insert(
+                            "Content-Type",
+                            format!("multipart/byteranges; boundary={boundary}"),
+                        );
+                        status_code = StatusCode::PartialContent;
+
+                        multipart_parts(ranges, total, &mime_type, &boundary)
+                    }
+                };
+
+                content = match res_data.content {
+                    FileContent::Bytes(content) => Body::Shared { content, parts },
+                    FileContent::Stream { file, .. } => Body::File { file, parts },
+                };
+
+                headers.insert("Content-Length", content.len().to_string());
             }
             ResponseType::Dir(res_data) => {
                 match self.config.format_directory_listing_as_json {
@@ -123,8 +196,7 @@ impl<'a> ResponseBuilder<'a> {
                             let item = match item {
                                 Ok(item) => item,
                                 Err(_) => {
-                                    return Err(ServerError::IOError(Error::new(
-                                        ErrorKind::Other,
+                                    return Err(ServerError::IOError(Error::other(
                                         "Failed reading directory item",
                                     )))
                                 }
@@ -134,20 +206,17 @@ impl<'a> ResponseBuilder<'a> {
                             let filename =
                                 match filename.to_str() {
                                     Some(str) => str,
-                                    None => return Err(ServerError::IOError(io::Error::new(
-                                        ErrorKind::Other,
+                                    None => return Err(ServerError::IOError(Error::other(
                                         "Failed parsing requested file name from OsString to str.",
                                     ))),
                                 };
 
-                            let decorated_filename = format!("{filename}");
-
-                            dirs.push(decorated_filename);
+                            dirs.push(filename.to_string());
                         }
 
                         if dirs.is_empty() {
                             body.push("<p>The requested directory is empty.</p>".into());
-                            status_code = 404;
+                            status_code = StatusCode::NotFound;
                         } else {
                             body.push("<p>Directory contents:</p>".into());
                             body.push("<ul>".into());
@@ -160,18 +229,20 @@ impl<'a> ResponseBuilder<'a> {
 
                             body.push("</ul>".to_string());
 
-                            let doc_len = build_html(head.iter().map(|s| s.as_str()).collect::<Vec<&str>>(), body.iter().map(|s| s.as_str()).collect::<Vec<&str>>()).len();
+                            status_code = StatusCode::Ok;
+                        }
 
-                            // Apply necessary headers and security headers
-                            headers.insert("Content-Type".into(), "text/html".into());
-                            headers.insert("Content-Length".into(), doc_len.to_string());
+                        let doc = build_html(head.iter().map(|s| s.as_str()).collect::<Vec<&str>>(), body.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
 
-                            status_code = 200;
-                        }
+                        // Apply necessary headers and security headers
+                        headers.insert("Content-Type", self.config.mime_types.get("html"));
+                        headers.insert("Content-Length", doc.len().to_string());
+
+                        content = Body::Bytes(doc.into_bytes());
                     }
                     true => {
                         // Apply necessary headers and security headers
-                        headers.insert("Content-Type".into(), "application/json".into());
+                        headers.insert("Content-Type", self.config.mime_types.get("json"));
 
                         let mut dirs: Vec<String> = vec![];
 
@@ -179,8 +250,7 @@ impl<'a> ResponseBuilder<'a> {
                             let item = match item {
                                 Ok(item) => item,
                                 Err(_) => {
-                                    return Err(ServerError::IOError(Error::new(
-                                        ErrorKind::Other,
+                                    return Err(ServerError::IOError(Error::other(
                                         "Failed reading directory item",
                                     )))
                                 }
@@ -190,8 +260,7 @@ impl<'a> ResponseBuilder<'a> {
                             let filename =
                                 match filename.to_str() {
                                     Some(str) => str,
-                                    None => return Err(ServerError::IOError(io::Error::new(
-                                        ErrorKind::Other,
+                                    None => return Err(ServerError::IOError(Error::other(
                                         "Failed parsing requested file name from OsString to str.",
                                     ))),
                                 };
@@ -203,36 +272,48 @@ impl<'a> ResponseBuilder<'a> {
                             let json =
                                 HashMap::from([("response", "The requested directory is empty.")]);
                             let json = map_as_json_str(json);
-                            status_code = 404;
-                            headers.insert("Content-Length".into(), json.len().to_string());
-                            body.push(json.to_string());
+                            status_code = StatusCode::NotFound;
+                            headers.insert("Content-Length", json.len().to_string());
+                            content = Body::Bytes(json.into_bytes());
                         } else {
                             let json = vec_as_json_str(dirs);
 
-                            headers.insert("Content-Length".into(), json.len().to_string());
+                            headers.insert("Content-Length", json.len().to_string());
 
-                            status_code = 200;
+                            status_code = StatusCode::Ok;
 
-                            body.push(json.to_string());
+                            content = Body::Bytes(json.into_bytes());
                         }
                     }
                 }
             }
+            ResponseType::Empty => {
+                // Every response except 204 and 304 needs to tell the client where it ends so the
+                // connection can be reused.
+                if !self.status_code.forbids_body() {
+                    headers.insert("Content-Length", "0");
+                }
+                content = Body::empty();
+                status_code = self.status_code;
+            }
             ResponseType::Fallback => {
-                let fallback_file = match fs::read_to_string([self.config.absolute_static_content_path, "/", self.status_code.to_string().as_str(), ".html"].concat()) {
+                // Fallback pages are optional, a minimal page is generated for the status codes
+                // which don't have one in the static content directory.
+                let fallback_file = match fs::read([self.config.absolute_static_content_path.as_str(), "/", self.status_code.to_string().as_str(), ".html"].concat()) {
                     Ok(file) => file,
-                    Err(_) => return Err(ServerError::from(io::Error::new(io::ErrorKind::NotFound, format!("Fallback file for {} status code page doesn't exist. Have you run 'make migrate'?", self.status_code))))
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                        default_fallback_page(self.status_code).into_bytes()
+                    }
+                    Err(e) => return Err(ServerError::from(e)),
                 };
 
-                headers.insert("Content-Type".into(), "text/html".into());
-                headers.insert("Content-Length".into(), fallback_file.len().to_string());
-                body.push(fallback_file);
+                headers.insert("Content-Type", self.config.mime_types.get("html"));
+                headers.insert("Content-Length", fallback_file.len().to_string());
+                content = Body::Bytes(fallback_file);
                 status_code = self.status_code;
             }
         }
 
-        let doc = build_html(head.iter().map(|s| s.as_str()).collect::<Vec<&str>>(), body.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
-
         let mut res = String::new();
 
         res.push_str(protocol);
@@ -240,23 +321,38 @@ impl<'a> ResponseBuilder<'a> {
         res.push_str(status_code.to_string().as_str());
         res.push(' ');
 
-        res.push_str(match status_code {
-            200 => "OK",
-            400 => "Bad Request",
-            404 => "Not Found",
-            405 => "Method Not Allowed",
-            500 => "Internal Server Error",
-            _ => return Err(ServerError::from(io::Error::new(io::ErrorKind::InvalidInput, "Invalid status code provided. This should not occur under any circumstance in production, if this has occurred please report it on GitHub."))),
-        });
+        res.push_str(status_code.reason_phrase());
 
-        for (key, val) in headers {
+        for (key, val) in headers.iter() {
             res.push_str("\r\n");
             res.push_str(format!("{}:{}", key, val).as_str());
         }
 
         res.push_str("\r\n\r\n");
-        res.push_str(&doc);
 
-        Ok(res)
+        let body = match self.method {
+            Method::Head => Body::empty(),
+            _ => content,
+        };
+
+        Ok(Response {
+            status_code,
+            head: res.into_bytes(),
+            body,
+            keep_alive: self.keep_alive,
+        })
     }
 }
+
+/// The page sent for status codes which have no `<code>.html` fallback page.
+fn default_fallback_page(status_code: StatusCode) -> String {
+    let title = format!("{} {}", status_code, status_code.reason_phrase());
+
+    build_html(
+        vec![
+            "<meta charset=\"utf-8\">",
+            format!("<title>{title}</title>").as_str(),
+        ],
+        vec![format!("<h1>{title}</h1>").as_str()],
+    )
+}
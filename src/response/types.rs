@@ -1,4 +1,7 @@
+use crate::compression::Encoding;
+use crate::range::ByteRange;
 use std::fs;
+use std::sync::Arc;
 
 /// The type of the content that will be sent back to the request.
 ///
@@ -6,15 +9,43 @@ use std::fs;
 /// `Self::Dir`: a response with the contents of a specific directory from the filesystem.
 /// `Self::Fallback`: used when you want to return a status code page back to the user, usually
 /// because of an error.
+/// `Self::Empty`: a response which only consists of headers, such as `204 No Content`.
 pub enum ResponseType<'a> {
     File(FileResponse<'a>),
     Dir(DirResponse),
     Fallback,
+    Empty,
 }
 
 pub struct FileResponse<'a> {
     pub file_ext: &'a str,
-    pub file_content: &'a str,
+    pub content: FileContent,
+    /// The byte ranges which should be sent instead of the whole content. Empty if the whole
+    /// content should be sent.
+    pub ranges: Vec<ByteRange>,
+    /// The content coding `file_content` is compressed with, if any.
+    pub encoding: Option<Encoding>,
+}
+
+/// Where the content of a file response comes from.
+pub enum FileContent {
+    /// A file held in memory, such as a cached one.
+    Bytes(Arc<[u8]>),
+    /// A file which is too large to be held in memory and is streamed from the disk instead.
+    Stream { file: fs::File, length: u64 },
+}
+
+impl FileContent {
+    pub fn len(&self) -> u64 {
+        match self {
+            FileContent::Bytes(bytes) => bytes.len() as u64,
+            FileContent::Stream { length, .. } => *length,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 pub struct DirResponse {
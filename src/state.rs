@@ -1,13 +1,26 @@
-use crate::file::CachedFile;
-use std::collections::HashMap;
+use crate::cache::FileCache;
+use crate::core::configuration::Configuration;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Clone)]
+/// State shared by every connection. Cloning it is cheap, clones share the same file cache.
+#[derive(Clone, Default)]
 pub struct AppState {
-    pub cached_files: Option<HashMap<String, CachedFile>>,
+    pub file_cache: Option<Arc<FileCache>>,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self { cached_files: None }
+impl AppState {
+    /// Creates the state of a server using `config`, with an empty file cache if caching is
+    /// enabled.
+    pub fn new(config: &Configuration) -> Self {
+        Self {
+            file_cache: config.cache_files.then(|| {
+                Arc::new(FileCache::new(
+                    config.cache_size,
+                    config.cache_max_file_size as usize,
+                    Duration::from_secs(config.cache_revalidate_after),
+                ))
+            }),
+        }
     }
 }
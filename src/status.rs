@@ -1 +1,255 @@
-pub type StatusCode = u16;
+use std::fmt;
+
+/// The HTTP status codes registered in the IANA "HTTP Status Code Registry".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum StatusCode {
+    Continue = 100,
+    SwitchingProtocols = 101,
+    Processing = 102,
+    EarlyHints = 103,
+    Ok = 200,
+    Created = 201,
+    Accepted = 202,
+    NonAuthoritativeInformation = 203,
+    NoContent = 204,
+    ResetContent = 205,
+    PartialContent = 206,
+    MultiStatus = 207,
+    AlreadyReported = 208,
+    ImUsed = 226,
+    MultipleChoices = 300,
+    MovedPermanently = 301,
+    Found = 302,
+    SeeOther = 303,
+    NotModified = 304,
+    UseProxy = 305,
+    TemporaryRedirect = 307,
+    PermanentRedirect = 308,
+    BadRequest = 400,
+    Unauthorized = 401,
+    PaymentRequired = 402,
+    Forbidden = 403,
+    NotFound = 404,
+    MethodNotAllowed = 405,
+    NotAcceptable = 406,
+    ProxyAuthenticationRequired = 407,
+    RequestTimeout = 408,
+    Conflict = 409,
+    Gone = 410,
+    LengthRequired = 411,
+    PreconditionFailed = 412,
+    ContentTooLarge = 413,
+    UriTooLong = 414,
+    UnsupportedMediaType = 415,
+    RangeNotSatisfiable = 416,
+    ExpectationFailed = 417,
+    MisdirectedRequest = 421,
+    UnprocessableContent = 422,
+    Locked = 423,
+    FailedDependency = 424,
+    TooEarly = 425,
+    UpgradeRequired = 426,
+    PreconditionRequired = 428,
+    TooManyRequests = 429,
+    RequestHeaderFieldsTooLarge = 431,
+    UnavailableForLegalReasons = 451,
+    InternalServerError = 500,
+    NotImplemented = 501,
+    BadGateway = 502,
+    ServiceUnavailable = 503,
+    GatewayTimeout = 504,
+    HttpVersionNotSupported = 505,
+    VariantAlsoNegotiates = 506,
+    InsufficientStorage = 507,
+    LoopDetected = 508,
+    NotExtended = 510,
+    NetworkAuthenticationRequired = 511,
+}
+
+impl StatusCode {
+    /// Every registered status code, in ascending order.
+    pub const ALL: [StatusCode; 61] = [
+        Self::Continue,
+        Self::SwitchingProtocols,
+        Self::Processing,
+        Self::EarlyHints,
+        Self::Ok,
+        Self::Created,
+        Self::Accepted,
+        Self::NonAuthoritativeInformation,
+        Self::NoContent,
+        Self::ResetContent,
+        Self::PartialContent,
+        Self::MultiStatus,
+        Self::AlreadyReported,
+        Self::ImUsed,
+        Self::MultipleChoices,
+        Self::MovedPermanently,
+        Self::Found,
+        Self::SeeOther,
+        Self::NotModified,
+        Self::UseProxy,
+        Self::TemporaryRedirect,
+        Self::PermanentRedirect,
+        Self::BadRequest,
+        Self::Unauthorized,
+        Self::PaymentRequired,
+        Self::Forbidden,
+        Self::NotFound,
+        Self::MethodNotAllowed,
+        Self::NotAcceptable,
+        Self::ProxyAuthenticationRequired,
+        Self::RequestTimeout,
+        Self::Conflict,
+        Self::Gone,
+        Self::LengthRequired,
+        Self::PreconditionFailed,
+        Self::ContentTooLarge,
+        Self::UriTooLong,
+        Self::UnsupportedMediaType,
+        Self::RangeNotSatisfiable,
+        Self::ExpectationFailed,
+        Self::MisdirectedRequest,
+        Self::UnprocessableContent,
+        Self::Locked,
+        Self::FailedDependency,
+        Self::TooEarly,
+        Self::UpgradeRequired,
+        Self::PreconditionRequired,
+        Self::TooManyRequests,
+        Self::RequestHeaderFieldsTooLarge,
+        Self::UnavailableForLegalReasons,
+        Self::InternalServerError,
+        Self::NotImplemented,
+        Self::BadGateway,
+        Self::ServiceUnavailable,
+        Self::GatewayTimeout,
+        Self::HttpVersionNotSupported,
+        Self::VariantAlsoNegotiates,
+        Self::InsufficientStorage,
+        Self::LoopDetected,
+        Self::NotExtended,
+        Self::NetworkAuthenticationRequired,
+    ];
+
+    pub fn as_u16(&self) -> u16 {
+        *self as u16
+    }
+
+    /// Returns the status code for `code`, or `None` if it isn't registered.
+    pub fn from_u16(code: u16) -> Option<Self> {
+        Self::ALL.iter().copied().find(|status| status.as_u16() == code)
+    }
+
+    pub fn reason_phrase(&self) -> &'static str {
+        match self {
+            Self::Continue => "Continue",
+            Self::SwitchingProtocols => "Switching Protocols",
+            Self::Processing => "Processing",
+            Self::EarlyHints => "Early Hints",
+            Self::Ok => "OK",
+            Self::Created => "Created",
+            Self::Accepted => "Accepted",
+            Self::NonAuthoritativeInformation => "Non-Authoritative Information",
+            Self::NoContent => "No Content",
+            Self::ResetContent => "Reset Content",
+            Self::PartialContent => "Partial Content",
+            Self::MultiStatus => "Multi-Status",
+            Self::AlreadyReported => "Already Reported",
+            Self::ImUsed => "IM Used",
+            Self::MultipleChoices => "Multiple Choices",
+            Self::MovedPermanently => "Moved Permanently",
+            Self::Found => "Found",
+            Self::SeeOther => "See Other",
+            Self::NotModified => "Not Modified",
+            Self::UseProxy => "Use Proxy",
+            Self::TemporaryRedirect => "Temporary Redirect",
+            Self::PermanentRedirect => "Permanent Redirect",
+            Self::BadRequest => "Bad Request",
+            Self::Unauthorized => "Unauthorized",
+            Self::PaymentRequired => "Payment Required",
+            Self::Forbidden => "Forbidden",
+            Self::NotFound => "Not Found",
+            Self::MethodNotAllowed => "Method Not Allowed",
+            Self::NotAcceptable => "Not Acceptable",
+            Self::ProxyAuthenticationRequired => "Proxy Authentication Required",
+            Self::RequestTimeout => "Request Timeout",
+            Self::Conflict => "Conflict",
+            Self::Gone => "Gone",
+            Self::LengthRequired => "Length Required",
+            Self::PreconditionFailed => "Precondition Failed",
+            Self::ContentTooLarge => "Content Too Large",
+            Self::UriTooLong => "URI Too Long",
+            Self::UnsupportedMediaType => "Unsupported Media Type",
+            Self::RangeNotSatisfiable => "Range Not Satisfiable",
+            Self::ExpectationFailed => "Expectation Failed",
+            Self::MisdirectedRequest => "Misdirected Request",
+            Self::UnprocessableContent => "Unprocessable Content",
+            Self::Locked => "Locked",
+            Self::FailedDependency => "Failed Dependency",
+            Self::TooEarly => "Too Early",
+            Self::UpgradeRequired => "Upgrade Required",
+            Self::PreconditionRequired => "Precondition Required",
+            Self::TooManyRequests => "Too Many Requests",
+            Self::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            Self::UnavailableForLegalReasons => "Unavailable For Legal Reasons",
+            Self::InternalServerError => "Internal Server Error",
+            Self::NotImplemented => "Not Implemented",
+            Self::BadGateway => "Bad Gateway",
+            Self::ServiceUnavailable => "Service Unavailable",
+            Self::GatewayTimeout => "Gateway Timeout",
+            Self::HttpVersionNotSupported => "HTTP Version Not Supported",
+            Self::VariantAlsoNegotiates => "Variant Also Negotiates",
+            Self::InsufficientStorage => "Insufficient Storage",
+            Self::LoopDetected => "Loop Detected",
+            Self::NotExtended => "Not Extended",
+            Self::NetworkAuthenticationRequired => "Network Authentication Required",
+        }
+    }
+
+    /// 1xx: the request was received and is being processed.
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.as_u16())
+    }
+
+    /// 2xx: the request was successfully received, understood and accepted.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.as_u16())
+    }
+
+    /// 3xx: further action needs to be taken to complete the request.
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.as_u16())
+    }
+
+    /// 4xx: the request contains bad syntax or cannot be fulfilled.
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    /// 5xx: the server failed to fulfill an apparently valid request.
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.as_u16())
+    }
+
+    /// Whether a response with this status code must not have a body.
+    pub fn forbids_body(&self) -> bool {
+        self.is_informational() || *self == Self::NoContent || *self == Self::NotModified
+    }
+}
+
+impl fmt::Display for StatusCode {
+    /// Formats the numeric code, e.g. `404`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_u16())
+    }
+}
+
+impl TryFrom<u16> for StatusCode {
+    type Error = u16;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        Self::from_u16(code).ok_or(code)
+    }
+}
@@ -1,4 +1,5 @@
-use std::sync::{mpsc, Arc, Mutex};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
 use std::thread;
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
@@ -42,10 +43,16 @@ impl ThreadPool {
 impl Worker {
     fn new(id: usize, rx: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
         let thread = thread::spawn(move || loop {
-            let message = rx.lock().unwrap().recv().unwrap();
+            // The pool is gone once the channel is closed.
+            let message = match rx.lock().unwrap_or_else(PoisonError::into_inner).recv() {
+                Ok(message) => message,
+                Err(_) => break,
+            };
             match message {
                 Message::NewJob(job) => {
-                    job();
+                    // A job which panics only ends its own connection, the worker carries on with
+                    // the next job so that the pool never runs out of threads.
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
                 } /* New messages may be added in a future update for server improving graceful shutdown */
             }
         });
@@ -1,6 +1,146 @@
-use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, SystemTimeError, UNIX_EPOCH};
 
 pub fn generate_unixtime() -> Result<u64, SystemTimeError> {
     let time = SystemTime::now().duration_since(UNIX_EPOCH)?;
     Ok(time.as_secs())
 }
+
+const DAY_NAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Converts days since the Unix epoch into a (year, month, day) civil date.
+///
+/// Algorithm from Howard Hinnant's "chrono-Compatible Low-Level Date Algorithms".
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Splits a time into days since the Unix epoch and seconds since the start of that day.
+fn days_and_secs(time: SystemTime) -> (i64, i64) {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+
+    (secs.div_euclid(86400), secs.rem_euclid(86400))
+}
+
+/// Formats a time as an HTTP-date (IMF-fixdate), e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub fn format_http_date(time: SystemTime) -> String {
+    let (days, secs_of_day) = days_and_secs(time);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        DAY_NAMES[days.rem_euclid(7) as usize],
+        day,
+        MONTH_NAMES[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+/// Formats a time the way the Common Log Format does, e.g. `06/Nov/1994:08:49:37 +0000`. Times
+/// are always in UTC.
+pub fn format_clf_date(time: SystemTime) -> String {
+    let (days, secs_of_day) = days_and_secs(time);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:02}/{}/{:04}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTH_NAMES[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+/// Formats a time as an ISO 8601 date and time in UTC, e.g. `1994-11-06T08:49:37Z`.
+pub fn format_iso8601(time: SystemTime) -> String {
+    let (days, secs_of_day) = days_and_secs(time);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+/// Parses an HTTP-date in the IMF-fixdate format. The obsolete RFC 850 and asctime formats are
+/// not supported since no current client sends them.
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+    let (_, date) = date.split_once(", ")?;
+    let mut parts = date.split(' ');
+
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTH_NAMES.iter().position(|name| *name == month)? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+
+    let mut time = parts.next()?.split(':');
+    let hours: u64 = time.next()?.parse().ok()?;
+    let minutes: u64 = time.next()?.parse().ok()?;
+    let seconds: u64 = time.next()?.parse().ok()?;
+
+    if parts.next()? != "GMT" || parts.next().is_some() || time.next().is_some() {
+        return None;
+    }
+
+    // Four digit years keep the arithmetic below far away from overflowing, a client sending a
+    // date that far off can't be trusted anyway.
+    if !(1..=31).contains(&day)
+        || !(1970..=9999).contains(&year)
+        || hours > 23
+        || minutes > 59
+        || seconds > 60
+    {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+
+    let secs = days
+        .checked_mul(86400)?
+        .checked_add(hours * 3600 + minutes * 60 + seconds)?;
+
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// Truncates a time to whole seconds, the precision of HTTP-dates.
+pub fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => UNIX_EPOCH + Duration::from_secs(duration.as_secs()),
+        Err(_) => time,
+    }
+}
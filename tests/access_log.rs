@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, UNIX_EPOCH};
+    use vrs::access_log::{AccessLogEntry, LogFormat};
+    use vrs::request::Request;
+    use vrs::status::StatusCode;
+
+    fn request() -> Request {
+        Request::read_from(Cursor::new(
+            b"GET /docs/index.html?lang=en HTTP/1.1\r\nHost:localhost\r\nReferer:http://example.com/\r\nUser-Agent:curl/8.0 \"quoted\"\r\n\r\n".to_vec(),
+        ))
+        .expect("request should be valid")
+    }
+
+    fn entry(request: Option<&Request>) -> AccessLogEntry<'_> {
+        AccessLogEntry {
+            client: Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 7))),
+            time: UNIX_EPOCH + Duration::from_secs(784111777),
+            request,
+            status: StatusCode::Ok,
+            bytes_sent: 2326,
+            latency: Duration::from_micros(1500),
+        }
+    }
+
+    fn format(format: &str, entry: &AccessLogEntry) -> String {
+        format.parse::<LogFormat>().unwrap().format(entry)
+    }
+
+    #[test]
+    fn common_and_combined_formats() {
+        let request = request();
+
+        assert_eq!(
+            format("common", &entry(Some(&request))),
+            "192.168.1.7 - - [06/Nov/1994:08:49:37 +0000] \"GET /docs/index.html?lang=en HTTP/1.1\" 200 2326 1500\n"
+        );
+        assert_eq!(
+            format("combined", &entry(Some(&request))),
+            "192.168.1.7 - - [06/Nov/1994:08:49:37 +0000] \"GET /docs/index.html?lang=en HTTP/1.1\" 200 2326 \"http://example.com/\" \"curl/8.0 \\\"quoted\\\"\" 1500\n"
+        );
+
+        // Requests which couldn't be parsed and empty bodies.
+        let entry = AccessLogEntry {
+            client: None,
+            status: StatusCode::BadRequest,
+            bytes_sent: 0,
+            ..entry(None)
+        };
+        assert_eq!(
+            format("combined", &entry),
+            "- - - [06/Nov/1994:08:49:37 +0000] \"-\" 400 - \"-\" \"-\" 1500\n"
+        );
+    }
+
+    #[test]
+    fn json_format() {
+        let request = request();
+
+        assert_eq!(
+            format("json", &entry(Some(&request))),
+            concat!(
+                r#"{"time":"1994-11-06T08:49:37Z","remote_addr":"192.168.1.7","#,
+                r#""request":"GET /docs/index.html?lang=en HTTP/1.1","method":"GET","#,
+                r#""uri":"/docs/index.html","query":"lang=en","protocol":"HTTP/1.1","#,
+                r#""status":200,"body_bytes_sent":2326,"request_time":0.001500,"#,
+                r#""referer":"http://example.com/","user_agent":"curl/8.0 \"quoted\""}"#,
+                "\n"
+            )
+        );
+
+        assert!(format("json", &entry(None)).contains(r#""request":null,"method":null"#));
+    }
+
+    #[test]
+    fn custom_format() {
+        let request = request();
+
+        assert_eq!(
+            format(
+                "$remote_addr $request_method $uri?$args ${status}s $body_bytes_sent $request_time $http_user_agent $http_x_missing $$",
+                &entry(Some(&request)),
+            ),
+            "192.168.1.7 GET /docs/index.html?lang=en 200s 2326 0.001 curl/8.0 \\\"quoted\\\" - $\n"
+        );
+    }
+
+    #[test]
+    fn invalid_custom_formats_are_rejected() {
+        assert!("$remote_addr $unknown".parse::<LogFormat>().is_err());
+        assert!("$ alone".parse::<LogFormat>().is_err());
+        assert!("${status".parse::<LogFormat>().is_err());
+        assert!("$http_".parse::<LogFormat>().is_err());
+    }
+}
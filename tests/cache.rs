@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+    use vrs::cache::{entry_size, FileCache, Lookup, ENTRY_OVERHEAD};
+    use vrs::compression::Encoding;
+    use vrs::file::CachedFile;
+
+    fn cached_file(content: &[u8], modified: Option<SystemTime>) -> CachedFile {
+        CachedFile {
+            extension: "txt".to_string(),
+            content: content.into(),
+            modified,
+            etag: "\"test\"".to_string(),
+            encoded: Default::default(),
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vrs-cache-test-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn least_recently_used_files_are_evicted() {
+        // Room for two entries with single letter keys and 9 bytes of content.
+        let cache = FileCache::new(2 * entry_size("a", 9) + 5, 100, Duration::from_secs(3600));
+        let path = Path::new("/nonexistent");
+
+        cache.insert("a", cached_file(&[0; 9], None));
+        cache.insert("b", cached_file(&[0; 9], None));
+        assert!(matches!(cache.lookup("a", path), Lookup::Hit(_)));
+
+        // "b" is the least recently used entry now.
+        cache.insert("c", cached_file(&[0; 9], None));
+        assert!(matches!(cache.lookup("b", path), Lookup::Miss));
+        assert!(matches!(cache.lookup("a", path), Lookup::Hit(_)));
+        assert!(matches!(cache.lookup("c", path), Lookup::Hit(_)));
+
+        // Too large for a single entry, the file is handed back without being cached.
+        let file = cache.insert("d", cached_file(&[0; 101], None));
+        assert_eq!(file.content.len(), 101);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.size, 2 * entry_size("a", 9));
+    }
+
+    #[test]
+    fn compressed_variants_count_towards_the_size() {
+        let cache = FileCache::new(1000, 1000, Duration::from_secs(3600));
+        let path = Path::new("/nonexistent");
+
+        let file = cache.insert("a", cached_file(&[b'a'; 100], None));
+        let compressed = cache.encode("a", &file, Encoding::Gzip).unwrap().len();
+        assert_eq!(cache.stats().size, entry_size("a", 100 + compressed));
+
+        // The compressed copy is shared with later lookups.
+        match cache.lookup("a", path) {
+            Lookup::Hit(file) => assert!(file.encoded(Encoding::Gzip).is_some()),
+            _ => panic!("expected a cache hit"),
+        }
+    }
+
+    #[test]
+    fn changed_files_are_revalidated() -> std::io::Result<()> {
+        let path = temp_path("changed");
+        std::fs::write(&path, "old")?;
+        let modified = std::fs::metadata(&path)?.modified().ok();
+
+        let cache = FileCache::new(1000, 1000, Duration::ZERO);
+        cache.insert("file", cached_file(b"old", modified));
+        assert!(matches!(cache.lookup("file", &path), Lookup::Hit(_)));
+
+        std::fs::write(&path, "new content")?;
+        assert!(matches!(cache.lookup("file", &path), Lookup::Miss));
+        assert_eq!(cache.stats().invalidations, 1);
+        assert_eq!(cache.stats().entries, 0);
+
+        std::fs::remove_file(&path)
+    }
+
+    #[test]
+    fn missing_files_are_cached() -> std::io::Result<()> {
+        let path = temp_path("missing");
+        let _ = std::fs::remove_file(&path);
+
+        let cache = FileCache::new(1000, 1000, Duration::ZERO);
+        cache.insert_not_found("missing");
+        assert!(matches!(cache.lookup("missing", &path), Lookup::NotFound));
+
+        // The file showing up invalidates the entry.
+        std::fs::write(&path, "here")?;
+        assert!(matches!(cache.lookup("missing", &path), Lookup::Miss));
+
+        std::fs::remove_file(&path)
+    }
+
+    #[test]
+    fn missing_files_are_charged_for_their_entry() {
+        let capacity = 100 * ENTRY_OVERHEAD;
+        let cache = FileCache::new(capacity, 1000, Duration::from_secs(3600));
+        let path = Path::new("/nonexistent");
+
+        // Every distinct missing path costs at least the overhead of an entry, so a flood of them
+        // only replaces older entries.
+        for i in 0..10_000 {
+            cache.insert_not_found(&format!("missing-{i}"));
+        }
+
+        let stats = cache.stats();
+        assert!(stats.entries < 100);
+        assert!(stats.size <= capacity);
+        assert_eq!(stats.evictions, 10_000 - stats.entries as u64);
+
+        // The most recently used entries are kept.
+        assert!(matches!(cache.lookup("missing-9999", path), Lookup::NotFound));
+        assert!(matches!(cache.lookup("missing-0", path), Lookup::Miss));
+    }
+
+    #[test]
+    fn recently_used_entries_survive_eviction() {
+        let cache = FileCache::new(3 * entry_size("a", 10), 100, Duration::from_secs(3600));
+        let path = Path::new("/nonexistent");
+
+        cache.insert("a", cached_file(&[0; 10], None));
+        cache.insert("b", cached_file(&[0; 10], None));
+        cache.insert("c", cached_file(&[0; 10], None));
+
+        // Used after they were inserted, "b" is the oldest entry now and "c" the next one.
+        assert!(matches!(cache.lookup("a", path), Lookup::Hit(_)));
+
+        cache.insert("d", cached_file(&[0; 10], None));
+        cache.insert("e", cached_file(&[0; 10], None));
+
+        assert!(matches!(cache.lookup("b", path), Lookup::Miss));
+        assert!(matches!(cache.lookup("c", path), Lookup::Miss));
+        assert!(matches!(cache.lookup("a", path), Lookup::Hit(_)));
+        assert!(matches!(cache.lookup("d", path), Lookup::Hit(_)));
+        assert!(matches!(cache.lookup("e", path), Lookup::Hit(_)));
+    }
+
+    #[test]
+    fn cache_is_shared_between_threads() {
+        let cache = Arc::new(FileCache::new(100_000, 1000, Duration::from_secs(3600)));
+        let path = Path::new("/nonexistent");
+
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let cache = Arc::clone(&cache);
+
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        let key = format!("file-{}", (worker + i) % 10);
+
+                        if let Lookup::Miss = cache.lookup(&key, path) {
+                            cache.insert(&key, cached_file(&[b'a'; 100], None));
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 800);
+        assert_eq!(stats.entries, 10);
+        assert_eq!(stats.size, 10 * entry_size("file-0", 100));
+    }
+}
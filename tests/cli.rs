@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use vrs::cli::{parse_args, CliError, Command};
+    use vrs::core::configuration::Configuration;
+    use vrs::core::server;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn overrides_are_applied() -> Result<(), CliError> {
+        let command = parse_args(args(&[
+            "--addr",
+            "127.0.0.1",
+            "--port=8080",
+            "--root",
+            "media/",
+            "--threads",
+            "4",
+            "--no-cache",
+            "--listing=json",
+        ]))?;
+
+        let config = match command {
+            Command::Run(config) => config,
+            _ => panic!("expected the run command"),
+        };
+
+        assert_eq!(config.addr, "127.0.0.1");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.absolute_static_content_path, "media");
+        assert!(config.multithreading);
+        assert_eq!(config.num_of_threads, 4);
+        assert!(!config.cache_files);
+        assert!(config.allow_directory_listing);
+        assert!(config.format_directory_listing_as_json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_config_reports_missing_fallback_pages() -> Result<(), CliError> {
+        let root = std::env::temp_dir().join("vrs-cli-check-config-test");
+        std::fs::create_dir_all(&root).expect("failed creating the temp dir");
+
+        let command = parse_args(args(&[
+            "--root",
+            root.to_str().expect("Temp dir path should be valid UTF-8"),
+            "--check-config",
+        ]))?;
+
+        let config = match command {
+            Command::CheckConfig(config) => config,
+            _ => panic!("expected the check-config command"),
+        };
+
+        let problems = config.validate();
+        assert!(problems.iter().any(|problem| problem.contains("404.html")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reopen_logs_uses_the_logs_directory() -> Result<(), CliError> {
+        let logs = std::env::temp_dir().join("vrs-cli-reopen-logs-test");
+        std::fs::create_dir_all(&logs).expect("failed creating the temp dir");
+
+        let config = match parse_args(args(&["--reopen-logs"]))? {
+            Command::ReopenLogs(config) => config,
+            _ => panic!("expected the reopen-logs command"),
+        };
+
+        let config = Configuration {
+            absolute_logs_path: logs.to_string_lossy().into_owned(),
+            ..config
+        };
+
+        server::request_log_reopen(&config).expect("failed creating the trigger file");
+        assert!(logs.join(server::LOG_REOPEN_TRIGGER).is_file());
+
+        std::fs::remove_dir_all(&logs).expect("failed removing the temp dir");
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert!(matches!(parse_args(args(&["--bogus"])), Err(CliError::Usage(_))));
+        assert!(matches!(parse_args(args(&["--port"])), Err(CliError::Usage(_))));
+        assert!(matches!(parse_args(args(&["--port", "http"])), Err(CliError::Usage(_))));
+        assert!(matches!(parse_args(args(&["--listing=tree"])), Err(CliError::Usage(_))));
+        assert!(matches!(parse_args(args(&["--help"])), Ok(Command::Help)));
+    }
+}
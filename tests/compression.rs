@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod tests {
+    use vrs::compression::{self, adler32, crc32, negotiate, Encoding};
+
+    /// Minimal DEFLATE decoder used to check that the encoder's output round-trips.
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        struct Bits<'a> {
+            data: &'a [u8],
+            pos: usize,
+        }
+
+        impl Bits<'_> {
+            fn read(&mut self, n: u32) -> u32 {
+                (0..n).fold(0, |value, i| {
+                    let bit = (self.data[self.pos / 8] >> (self.pos % 8)) & 1;
+                    self.pos += 1;
+                    value | (bit as u32) << i
+                })
+            }
+
+            fn decode(&mut self, lengths: &[u8]) -> usize {
+                // Canonical codes are consecutive within a length, so walking the lengths in order
+                // while reading one bit at a time finds the symbol.
+                let (mut code, mut first) = (0i32, 0i32);
+                for length in 1..=15 {
+                    code |= self.read(1) as i32;
+                    let count = lengths.iter().filter(|l| **l == length).count() as i32;
+                    if code - first < count {
+                        let n = (code - first) as usize;
+                        return (0..lengths.len())
+                            .filter(|s| lengths[*s] == length)
+                            .nth(n)
+                            .unwrap();
+                    }
+                    first = (first + count) << 1;
+                    code <<= 1;
+                }
+                panic!("invalid Huffman code");
+            }
+        }
+
+        const LENGTH_BASE: [usize; 29] = [
+            3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99,
+            115, 131, 163, 195, 227, 258,
+        ];
+        const DISTANCE_BASE: [usize; 30] = [
+            1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025,
+            1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+        ];
+
+        let mut bits = Bits { data, pos: 0 };
+        let mut out: Vec<u8> = vec![];
+
+        loop {
+            let last = bits.read(1) == 1;
+            assert_eq!(bits.read(2), 2, "only dynamic blocks are produced");
+
+            let num_literals = bits.read(5) as usize + 257;
+            let num_distances = bits.read(5) as usize + 1;
+            let num_code_lengths = bits.read(4) as usize + 4;
+
+            let mut code_length_lengths = [0u8; 19];
+            for symbol in &[
+                16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+            ][..num_code_lengths]
+            {
+                code_length_lengths[*symbol] = bits.read(3) as u8;
+            }
+
+            let mut lengths: Vec<u8> = vec![];
+            while lengths.len() < num_literals + num_distances {
+                match bits.decode(&code_length_lengths) {
+                    16 => {
+                        let prev = *lengths.last().unwrap();
+                        lengths.extend(std::iter::repeat_n(prev, 3 + bits.read(2) as usize));
+                    }
+                    17 => lengths.extend(std::iter::repeat_n(0, 3 + bits.read(3) as usize)),
+                    18 => lengths.extend(std::iter::repeat_n(0, 11 + bits.read(7) as usize)),
+                    length => lengths.push(length as u8),
+                }
+            }
+
+            let (literal_lengths, distance_lengths) = lengths.split_at(num_literals);
+
+            loop {
+                match bits.decode(literal_lengths) {
+                    256 => break,
+                    literal @ 0..=255 => out.push(literal as u8),
+                    symbol => {
+                        let code = symbol - 257;
+                        let extra = [
+                            0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4,
+                            5, 5, 5, 5, 0,
+                        ][code];
+                        let length = LENGTH_BASE[code] + bits.read(extra) as usize;
+                        let code = bits.decode(distance_lengths);
+                        let distance = DISTANCE_BASE[code]
+                            + bits.read((code as u32 / 2).saturating_sub(1)) as usize;
+                        for _ in 0..length {
+                            out.push(out[out.len() - distance]);
+                        }
+                    }
+                }
+            }
+
+            if last {
+                return out;
+            }
+        }
+    }
+
+    fn samples() -> Vec<Vec<u8>> {
+        let mut random = vec![];
+        let mut seed = 1u32;
+        for _ in 0..20_000 {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            random.push((seed >> 16) as u8);
+        }
+
+        let text = (0..5_000)
+            .map(|i| format!("<li class=\"item-{}\">Item {i}</li>\n", i % 37))
+            .collect::<String>()
+            .into_bytes();
+
+        vec![vec![], b"a".to_vec(), vec![0; 100_000], random, text]
+    }
+
+    #[test]
+    fn deflate_round_trips() {
+        for sample in samples() {
+            assert_eq!(inflate(&compression::deflate(&sample)), sample);
+        }
+
+        let text = samples().pop().unwrap();
+        assert!(compression::deflate(&text).len() < text.len() / 10);
+    }
+
+    #[test]
+    fn gzip_and_zlib_wrappers() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(crc32(data), 0x414fa339);
+        assert_eq!(adler32(data), 0x5bdc0fda);
+
+        let gzip = Encoding::Gzip.encode(data).unwrap();
+        assert_eq!(gzip[..3], [0x1f, 0x8b, 8]);
+        assert_eq!(inflate(&gzip[10..gzip.len() - 8]), data);
+        assert_eq!(
+            gzip[gzip.len() - 8..gzip.len() - 4],
+            crc32(data).to_le_bytes()
+        );
+        assert_eq!(gzip[gzip.len() - 4..], (data.len() as u32).to_le_bytes());
+
+        let zlib = Encoding::Deflate.encode(data).unwrap();
+        assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+        assert_eq!(inflate(&zlib[2..zlib.len() - 4]), data);
+        assert_eq!(zlib[zlib.len() - 4..], adler32(data).to_be_bytes());
+    }
+
+    #[test]
+    fn accept_encoding_negotiation() {
+        let all = Encoding::ALL;
+
+        assert_eq!(negotiate("gzip, deflate, br", &all), Some(Encoding::Gzip));
+        assert_eq!(negotiate("deflate", &all), Some(Encoding::Deflate));
+        assert_eq!(
+            negotiate("gzip;q=0.5, deflate", &all),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(negotiate("GZIP;Q=1.0", &all), Some(Encoding::Gzip));
+        assert_eq!(negotiate("x-gzip", &all), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*", &all), Some(Encoding::Gzip));
+        assert_eq!(
+            negotiate("gzip;q=0, *;q=0.1", &all),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(negotiate("gzip;q=0, deflate;q=0", &all), None);
+        assert_eq!(negotiate("gzip;q=2", &all), None);
+        assert_eq!(negotiate("identity", &all), None);
+        assert_eq!(negotiate("", &all), None);
+    }
+
+    #[test]
+    fn compressible_mime_types() {
+        let allowed = vec!["text/*".to_string(), "application/json".to_string()];
+
+        assert!(compression::is_compressible(
+            &allowed,
+            "text/html; charset=utf-8"
+        ));
+        assert!(compression::is_compressible(&allowed, "application/json"));
+        assert!(!compression::is_compressible(&allowed, "application/jsonp"));
+        assert!(!compression::is_compressible(&allowed, "image/png"));
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use vrs::core::config_file::ConfigError;
+    use vrs::core::configuration::Configuration;
+    use vrs::http::Method;
+
+    #[test]
+    fn file_overrides_defaults() -> Result<(), ConfigError> {
+        let config = Configuration::read_from_str(
+            "
+# Development settings
+[server]
+addr = 127.0.0.1
+port = 8080
+absolute_static_content_path = \"/srv/www/\"
+
+[cors]
+allowed_methods = get, HEAD
+extra_headers = Server: vrs, X-Test: a b
+",
+        )?;
+
+        assert_eq!(config.addr, "127.0.0.1");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.absolute_static_content_path, "/srv/www");
+        assert!(config.allowed_methods.contains(&Method::Get));
+        assert!(config.allowed_methods.contains(&Method::Head));
+        assert_eq!(
+            config.extra_headers,
+            vec![
+                ("Server".to_string(), "vrs".to_string()),
+                ("X-Test".to_string(), "a b".to_string())
+            ]
+        );
+        // Untouched options keep their defaults.
+        assert_eq!(
+            config.use_security_headers,
+            Configuration::read_from_vars().use_security_headers
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_key_reports_line() {
+        let err = Configuration::read_from_str("port = 8080\n\nprot = 80\n").err();
+
+        match err {
+            Some(ConfigError::Parse { line, message }) => {
+                assert_eq!(line, 3);
+                assert!(message.contains("prot"));
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn invalid_value_reports_line() {
+        let err = Configuration::read_from_str("cache_files = yes").err();
+        assert!(matches!(err, Some(ConfigError::Parse { line: 1, .. })));
+
+        let err = Configuration::read_from_str("allowed_methods = GET, FETCH").err();
+        assert!(matches!(err, Some(ConfigError::Parse { line: 1, .. })));
+    }
+
+    #[test]
+    fn spa_fallback_options() -> Result<(), ConfigError> {
+        let config = Configuration::read_from_str(
+            "
+spa_fallback = true
+spa_fallback_file = /app/index.html
+spa_include_prefixes = /app/, /admin/
+spa_exclude_prefixes = /app/assets/
+",
+        )?;
+
+        assert!(config.spa_fallback);
+        assert_eq!(config.spa_fallback_file, "app/index.html");
+        assert_eq!(config.spa_include_prefixes, vec!["/app/", "/admin/"]);
+        assert_eq!(config.spa_exclude_prefixes, vec!["/app/assets/"]);
+
+        for file in ["/", "../index.html", "app/"] {
+            let err = Configuration::read_from_str(&format!("spa_fallback_file = {file}")).err();
+            assert!(matches!(err, Some(ConfigError::Parse { line: 1, .. })), "{file}");
+        }
+
+        Ok(())
+    }
+}
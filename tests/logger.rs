@@ -0,0 +1,215 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+    use vrs::core::configuration::Configuration;
+    use vrs::core::server;
+    use vrs::error::ServerError;
+    use vrs::logger::{LogRotation, Logger};
+    use vrs::state::AppState;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vrs-logger-test-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn messages_from_every_thread_are_written() -> std::io::Result<()> {
+        let path = temp_path("threads");
+        let _ = std::fs::remove_file(&path);
+        let logger = Logger::open(&path, LogRotation::default())?;
+
+        let threads: Vec<_> = (0..4)
+            .map(|thread| {
+                let logger = logger.clone();
+
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        logger.log(format!("thread {thread} message {i}\n"));
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().expect("logging thread panicked");
+        }
+
+        logger.flush();
+
+        let log = std::fs::read_to_string(&path)?;
+        assert_eq!(log.lines().count(), 100);
+        assert!(log.lines().all(|line| line.starts_with("thread ")));
+
+        std::fs::remove_file(&path)
+    }
+
+    fn temp_dir(name: &str) -> std::io::Result<PathBuf> {
+        let dir = temp_path(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    fn rotated_files(dir: &Path) -> std::io::Result<Vec<String>> {
+        let mut names = vec![];
+
+        for entry in std::fs::read_dir(dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+
+            if name.starts_with("access.log.") {
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    #[test]
+    fn large_files_are_rotated() -> std::io::Result<()> {
+        let dir = temp_dir("rotation")?;
+        let path = dir.join("access.log");
+
+        let rotation = LogRotation {
+            max_size: 100,
+            daily: false,
+            max_files: 2,
+            compress: true,
+        };
+        let logger = Logger::open(&path, rotation)?;
+
+        // Two messages fit into every file.
+        for i in 0..10 {
+            logger.log(format!("message {i:02} {}\n", "x".repeat(28)));
+            logger.flush();
+        }
+
+        let rotated = rotated_files(&dir)?;
+        assert_eq!(rotated.len(), 2);
+
+        for name in rotated {
+            assert!(name.ends_with(".gz"));
+            let gzipped = std::fs::read(dir.join(name))?;
+            assert_eq!(gzipped[..2], [0x1f, 0x8b]);
+        }
+
+        let log = std::fs::read_to_string(&path)?;
+        assert_eq!(log.lines().count(), 2);
+        assert!(log.starts_with("message 08"));
+
+        std::fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn moved_files_are_reopened() -> std::io::Result<()> {
+        let dir = temp_dir("reopen")?;
+        let path = dir.join("access.log");
+        let logger = Logger::open(&path, LogRotation::default())?;
+
+        logger.log("before\n");
+        logger.flush();
+
+        // What logrotate does before asking the server to reopen its log.
+        std::fs::rename(&path, dir.join("moved.log"))?;
+        logger.reopen();
+
+        logger.log("after\n");
+        logger.flush();
+
+        assert_eq!(std::fs::read_to_string(dir.join("moved.log"))?, "before\n");
+        assert_eq!(std::fs::read_to_string(&path)?, "after\n");
+
+        std::fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn trigger_files_reopen_the_log() -> std::io::Result<()> {
+        let dir = temp_dir("trigger")?;
+        let path = dir.join("access.log");
+        let trigger = dir.join("reopen-logs");
+        let logger = Logger::open(&path, LogRotation::default())?;
+        logger.watch(&trigger);
+
+        logger.log("before\n");
+        logger.flush();
+
+        std::fs::rename(&path, dir.join("moved.log"))?;
+        std::fs::write(&trigger, "")?;
+
+        // The trigger is removed once the log has been reopened.
+        let start = Instant::now();
+
+        while trigger.exists() {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "trigger wasn't noticed"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        logger.log("after\n");
+        logger.flush();
+
+        assert_eq!(std::fs::read_to_string(dir.join("moved.log"))?, "before\n");
+        assert_eq!(std::fs::read_to_string(&path)?, "after\n");
+
+        std::fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn truncated_files_are_not_rotated_early() -> std::io::Result<()> {
+        let dir = temp_dir("truncate")?;
+        let path = dir.join("access.log");
+
+        let rotation = LogRotation {
+            max_size: 100,
+            ..LogRotation::default()
+        };
+        let logger = Logger::open(&path, rotation)?;
+
+        logger.log(format!("{}\n", "x".repeat(79)));
+        logger.flush();
+
+        // What logrotate does with `copytruncate`, the file stays the same.
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_len(0)?;
+
+        logger.log(format!("{}\n", "y".repeat(39)));
+        logger.flush();
+
+        assert!(rotated_files(&dir)?.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            format!("{}\n", "y".repeat(39))
+        );
+
+        std::fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn requests_are_logged() -> Result<(), ServerError> {
+        let path = temp_path("requests");
+        let _ = std::fs::remove_file(&path);
+        let logger = Logger::open(&path, LogRotation::default())?;
+
+        server::serve_request(
+            &Configuration::test_config(),
+            Some(&logger),
+            Cursor::new(b"GET /index.html HTTP/1.1\r\nOrigin:localhost\r\n\r\n".to_vec()),
+            &AppState::default(),
+        )?;
+
+        logger.flush();
+
+        let log = std::fs::read_to_string(&path)?;
+        assert!(log.starts_with("- - - ["));
+        assert!(log.contains("] \"GET /index.html HTTP/1.1\" 200 "));
+        assert_eq!(log.lines().count(), 1);
+
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+}
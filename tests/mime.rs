@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use vrs::core::configuration::Configuration;
+    use vrs::file::get_file_extension;
+    use vrs::mime::MimeTypes;
+
+    #[test]
+    fn builtin_mime_types() {
+        let mime_types = MimeTypes::new();
+
+        assert_eq!(mime_types.get("html"), "text/html; charset=utf-8");
+        assert_eq!(mime_types.get("JS"), "text/javascript; charset=utf-8");
+        assert_eq!(mime_types.get("svg"), "image/svg+xml; charset=utf-8");
+        assert_eq!(mime_types.get("woff2"), "font/woff2");
+        assert_eq!(mime_types.get("wasm"), "application/wasm");
+        assert_eq!(mime_types.get("unknown"), "application/octet-stream");
+        assert_eq!(mime_types.get(""), "application/octet-stream");
+    }
+
+    #[test]
+    fn file_extensions() {
+        assert_eq!(get_file_extension("index.HTML"), "html");
+        assert_eq!(get_file_extension("assets/app.min.js"), "js");
+        assert_eq!(get_file_extension("v1.2/README"), "");
+        assert_eq!(get_file_extension(".htaccess"), "");
+    }
+
+    #[test]
+    fn mime_types_overrides() {
+        let mime_types = MimeTypes::read_from_str(
+            "# comment\ntext/x-rust rs\n\napplication/toml toml;\nimage/x-png png PNG\n",
+        )
+        .unwrap();
+
+        assert_eq!(mime_types.get("rs"), "text/x-rust; charset=utf-8");
+        assert_eq!(mime_types.get("toml"), "application/toml");
+        assert_eq!(mime_types.get("png"), "image/x-png");
+        assert_eq!(mime_types.get("css"), "text/css; charset=utf-8");
+
+        let err = MimeTypes::read_from_str("text/plain txt\nrs\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: expected 'type/subtype extension...', found 'rs'"
+        );
+
+        let err = Configuration::read_from_str("mime_types_file = /nonexistent/mime.types");
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+    use vrs::range::{parse_range, ByteRange, RangeResult};
+    use vrs::time::{format_http_date, parse_http_date};
+
+    #[test]
+    fn parses_byte_ranges() {
+        assert_eq!(
+            parse_range("bytes=0-4", 10),
+            RangeResult::Partial(vec![ByteRange { start: 0, end: 4 }])
+        );
+        assert_eq!(
+            parse_range("bytes=5-, -3, 8-100", 10),
+            RangeResult::Partial(vec![
+                ByteRange { start: 5, end: 9 },
+                ByteRange { start: 7, end: 9 },
+                ByteRange { start: 8, end: 9 },
+            ])
+        );
+        assert_eq!(
+            parse_range("bytes=-20", 10),
+            RangeResult::Partial(vec![ByteRange { start: 0, end: 9 }])
+        );
+        assert_eq!(parse_range("bytes=10-", 10), RangeResult::NotSatisfiable);
+        assert_eq!(parse_range("bytes=-0", 10), RangeResult::NotSatisfiable);
+        assert_eq!(parse_range("bytes=5-2", 10), RangeResult::Full);
+        assert_eq!(parse_range("items=0-4", 10), RangeResult::Full);
+        assert_eq!(parse_range("bytes=a-b", 10), RangeResult::Full);
+    }
+
+    #[test]
+    fn http_dates_round_trip() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+
+        assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(time));
+        assert_eq!(
+            format_http_date(UNIX_EPOCH + Duration::from_secs(951782400)),
+            "Tue, 29 Feb 2000 00:00:00 GMT"
+        );
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+    }
+
+    #[test]
+    fn out_of_range_http_dates_are_rejected() {
+        assert_eq!(parse_http_date("Sun, 06 Nov 300000000000 08:49:37 GMT"), None);
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 9223372036854775807 08:49:37 GMT"),
+            None
+        );
+        assert_eq!(parse_http_date("Sun, 06 Nov 10000 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1969 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov -1994 08:49:37 GMT"), None);
+        assert!(parse_http_date("Fri, 31 Dec 9999 23:59:59 GMT").is_some());
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use vrs::http::{HttpProtocolVersion, Method};
+    use vrs::request::Request;
+    use vrs::status::StatusCode;
+
+    fn parse(raw: &str) -> Result<Request, StatusCode> {
+        Request::read_from(Cursor::new(raw.as_bytes().to_vec()))
+    }
+
+    #[test]
+    fn parses_request_line_and_headers() -> Result<(), StatusCode> {
+        let request = parse(
+            "OPTIONS /docs/index.html?v=3&lang=en HTTP/1.0\r\nHost: localhost\r\nAccept: text/html, */*\r\nx-custom:  a b \r\nX-Custom: c\r\n\r\n",
+        )?;
+
+        assert_eq!(request.method, Method::Options);
+        assert_eq!(request.target, "/docs/index.html?v=3&lang=en");
+        assert_eq!(request.path, "/docs/index.html");
+        assert_eq!(request.query.as_deref(), Some("v=3&lang=en"));
+        assert_eq!(request.version, HttpProtocolVersion::OneDotZero);
+        assert_eq!(request.headers.get("accept"), Some("text/html, */*"));
+        assert_eq!(
+            request.headers.get_all("X-CUSTOM").collect::<Vec<&str>>(),
+            vec!["a b", "c"]
+        );
+        assert!(!request.keep_alive());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_body_and_leaves_next_request() -> Result<(), StatusCode> {
+        let mut input = Cursor::new(
+            b"POST /form HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhelloGET / HTTP/1.1\r\nHost: localhost\r\n\r\n"
+                .to_vec(),
+        );
+
+        let first = Request::read_from(&mut input)?;
+        assert_eq!(first.body, b"hello");
+
+        let second = Request::read_from(&mut input)?;
+        assert_eq!(second.method, Method::Get);
+        assert_eq!(second.path, "/");
+
+        let mut input = Cursor::new(
+            b"PUT /upload HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n"
+                .to_vec(),
+        );
+        assert_eq!(Request::read_from(&mut input)?.body, b"Wikipedia");
+
+        Ok(())
+    }
+
+    #[test]
+    fn absolute_form_target_is_reduced_to_path() -> Result<(), StatusCode> {
+        let request = parse("GET http://localhost:8080/a/b?c HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+
+        assert_eq!(request.path, "/a/b");
+        assert_eq!(request.query.as_deref(), Some("c"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_requests_are_rejected() {
+        let malformed = [
+            "GET /\r\nHost: localhost\r\n\r\n",
+            "GET  / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET / HTTP/1.1 extra\r\nHost: localhost\r\n\r\n",
+            "GET index.html HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET * HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET / HTTP/1\r\nHost: localhost\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost : localhost\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: localhost\r\n folded\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 1, 2\r\n\r\nab",
+            "GET / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\nshort",
+            "GET / HTTP/1.1\r\nHost: localhost\r\n",
+        ];
+
+        for raw in malformed {
+            assert_eq!(parse(raw).err(), Some(StatusCode::BadRequest), "{raw:?}");
+        }
+
+        assert_eq!(
+            parse("BREW / HTTP/1.1\r\nHost: localhost\r\n\r\n").err(),
+            Some(StatusCode::MethodNotAllowed)
+        );
+        assert_eq!(
+            parse("GET / HTTP/3.0\r\nHost: localhost\r\n\r\n").err(),
+            Some(StatusCode::HttpVersionNotSupported)
+        );
+        assert_eq!(
+            parse("GET / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: gzip\r\n\r\n").err(),
+            Some(StatusCode::NotImplemented)
+        );
+
+        let huge_chunk = "PUT / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\nffffffffffffffff\r\nb\r\n0\r\n\r\n";
+        assert_eq!(parse(huge_chunk).err(), Some(StatusCode::ContentTooLarge));
+
+        let long_target = format!("GET /{} HTTP/1.1\r\nHost: localhost\r\n\r\n", "a".repeat(70 * 1024));
+        assert_eq!(parse(&long_target).err(), Some(StatusCode::UriTooLong));
+
+        let long_header = format!("GET / HTTP/1.1\r\nHost: localhost\r\nX-Big: {}\r\n\r\n", "a".repeat(70 * 1024));
+        assert_eq!(
+            parse(&long_header).err(),
+            Some(StatusCode::RequestHeaderFieldsTooLarge)
+        );
+    }
+}
@@ -1,31 +1,34 @@
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
-    use std::io::Read;
+    use std::io::{BufRead, IoSlice, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, UNIX_EPOCH};
     use vrs::core::configuration::Configuration;
     use vrs::core::server;
     use vrs::error::ServerError;
     use vrs::state::AppState;
 
-    // This is thread local to make it possible to get around the lack of a suitable once_cell in std.
-    // Note that this will leak memory in each thread that references it, and is therefore not well-suited for many tasks.
-    // In the future, this should be replaced with a OnceCell, or the Configuration struct should use Cow instead of &str.
-    thread_local! {
-        static LOGS_PATH: &'static str = Box::leak(
-            std::env::temp_dir()
-                .to_str()
-                .expect("Temp dir path should be valid UTF-8")
-                .to_string()
-                .into_boxed_str(),
-        );
-    }
-
-    fn test_serve_request(input: impl Read) -> Result<String, ServerError> {
-        server::serve_request(
+    fn test_serve_request(input: impl BufRead) -> Result<String, ServerError> {
+        let res = server::serve_request(
             &Configuration::test_config(),
             None,
             input,
-            &mut AppState::default(),
+            &AppState::default(),
+        )?;
+        Ok(String::from_utf8_lossy(&res.into_bytes()?).into_owned())
+    }
+
+    /// Splits a raw HTTP response into its head and its body.
+    fn split_response(res: &[u8]) -> (String, &[u8]) {
+        let end = res
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .expect("response has no end of headers");
+        (
+            String::from_utf8_lossy(&res[..end]).into_owned(),
+            &res[end + 4..],
         )
     }
 
@@ -43,6 +46,8 @@ mod tests {
             buf_utf8.push('\n');
         }
 
+        buf_utf8.push('\n');
+
         Cursor::new(buf_utf8.into_bytes())
     }
 
@@ -78,6 +83,14 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn http_1_0_without_headers_is_ok() -> Result<(), ServerError> {
+        let res = test_serve_request(Cursor::new(b"GET / HTTP/1.0\r\n\r\n".to_vec()))?;
+        assert_eq!(get_response_code(&res)?, 200);
+
+        Ok(())
+    }
+
     #[test]
     fn nonexistent_file_is_not_found() -> Result<(), ServerError> {
         let res = test_serve_request(create_test_buffer(
@@ -126,4 +139,964 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn binary_file_is_served_unchanged() -> Result<(), ServerError> {
+        let root = std::env::temp_dir().join("vrs-binary-file-test");
+        std::fs::create_dir_all(&root)?;
+        let content: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x00, 0xc6, 0xff, 0x0a];
+        std::fs::write(root.join("image.png"), &content)?;
+
+        let config = Configuration {
+            absolute_static_content_path: root
+                .to_str()
+                .expect("Temp dir path should be valid UTF-8")
+                .to_string(),
+            ..Configuration::test_config()
+        };
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer("GET /image.png HTTP/1.1", vec!["Origin:localhost"]),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains(&format!("Content-Length:{}", content.len())));
+        assert_eq!(body, content.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn connection_header_follows_http_version() -> Result<(), ServerError> {
+        let res = test_serve_request(create_test_buffer(
+            "GET / HTTP/1.1",
+            vec!["Origin:localhost"],
+        ))?;
+        assert!(res.contains("Connection:keep-alive"));
+
+        let res = test_serve_request(create_test_buffer(
+            "GET / HTTP/1.1",
+            vec!["Origin:localhost", "Connection:close"],
+        ))?;
+        assert!(res.contains("Connection:close"));
+
+        let res = test_serve_request(create_test_buffer(
+            "GET / HTTP/1.0",
+            vec!["Origin:localhost"],
+        ))?;
+        assert!(res.contains("Connection:close"));
+
+        let res = test_serve_request(create_test_buffer(
+            "GET / HTTP/1.0",
+            vec!["Origin:localhost", "Connection:keep-alive"],
+        ))?;
+        assert!(res.contains("Connection:keep-alive"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn head_and_body_are_written_at_once() -> Result<(), ServerError> {
+        /// Records the number of bytes of every write it's given.
+        struct Writes(Vec<usize>);
+
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.len());
+                Ok(buf.len())
+            }
+
+            fn write_vectored(&mut self, bufs: &[IoSlice]) -> std::io::Result<usize> {
+                let written = bufs.iter().map(|buf| buf.len()).sum();
+                self.0.push(written);
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut response = server::serve_request(
+            &Configuration::test_config(),
+            None,
+            create_test_buffer("GET / HTTP/1.1", vec!["Origin:localhost"]),
+            &AppState::default(),
+        )?;
+        let mut writes = Writes(vec![]);
+        response.write_to(&mut writes)?;
+
+        assert_eq!(writes.0.len(), 1);
+        assert_eq!(writes.0[0], response.head.len() + response.body.len() as usize);
+
+        Ok(())
+    }
+
+    #[test]
+    fn persistent_connection_serves_pipelined_requests() -> Result<(), ServerError> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        let client = std::thread::spawn(move || -> std::io::Result<String> {
+            let mut stream = TcpStream::connect(addr)?;
+            stream.write_all(
+                b"GET / HTTP/1.1\r\nOrigin:localhost\r\n\r\nGET /notfound HTTP/1.1\r\nOrigin:localhost\r\nConnection:close\r\n\r\n",
+            )?;
+            let mut res = String::new();
+            stream.read_to_string(&mut res)?;
+            Ok(res)
+        });
+
+        let (stream, _) = listener.accept()?;
+        server::serve_connection(
+            &Configuration::test_config(),
+            None,
+            &stream,
+            &AppState::default(),
+        );
+        drop(stream);
+
+        let res = client.join().expect("client thread panicked")?;
+        let status_lines: Vec<&str> = res.lines().filter(|line| line.starts_with("HTTP/")).collect();
+
+        assert_eq!(status_lines, vec!["HTTP/1.1 200 OK", "HTTP/1.1 404 Not Found"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn head_has_same_headers_as_get_without_body() -> Result<(), ServerError> {
+        for path in ["/", "/index.html", "/notfound"] {
+            let get = test_serve_request(create_test_buffer(
+                &format!("GET {path} HTTP/1.1"),
+                vec!["Origin:localhost"],
+            ))?;
+            let head = test_serve_request(create_test_buffer(
+                &format!("HEAD {path} HTTP/1.1"),
+                vec!["Origin:localhost"],
+            ))?;
+
+            let (get_head, get_body) = split_response(get.as_bytes());
+            let (head_head, head_body) = split_response(head.as_bytes());
+
+            assert_eq!(get_head, head_head);
+            assert!(get_head.contains(&format!("Content-Length:{}", get_body.len())));
+            assert!(!get_body.is_empty());
+            assert!(head_body.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn head_directory_listing_has_no_body() -> Result<(), ServerError> {
+        let config = Configuration {
+            absolute_static_content_path: ".".to_string(),
+            ..Configuration::test_config()
+        };
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer("HEAD /src/ HTTP/1.1", vec!["Origin:localhost"]),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains("Content-Type:text/html"));
+        assert!(!head.contains("Content-Length:0"));
+        assert!(body.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cors_preflight_is_answered() -> Result<(), ServerError> {
+        let config = Configuration {
+            cors_allowed_headers: vec!["Content-Type".to_string(), "X-Requested-With".to_string()],
+            cors_max_age: 600,
+            cors_allow_credentials: true,
+            ..Configuration::test_config()
+        };
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer(
+                "OPTIONS /index.html HTTP/1.1",
+                vec!["Origin:localhost", "Access-Control-Request-Method:GET"],
+            ),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 204);
+        assert!(head.contains("Access-Control-Allow-Origin:localhost"));
+        assert!(head.contains("Access-Control-Allow-Methods:GET, HEAD, OPTIONS"));
+        assert!(head.contains("Access-Control-Allow-Headers:Content-Type, X-Requested-With"));
+        assert!(head.contains("Access-Control-Max-Age:600"));
+        assert!(head.contains("Access-Control-Allow-Credentials:true"));
+        assert!(head.contains("Vary:Origin"));
+        assert!(body.is_empty());
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer(
+                "OPTIONS /index.html HTTP/1.1",
+                vec!["Origin:https://evil.example", "Access-Control-Request-Method:GET"],
+            ),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, _) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 204);
+        assert!(head.contains("Access-Control-Allow-Origin:null"));
+        assert!(head.contains("Vary:Origin"));
+        assert!(!head.contains("Access-Control-Allow-Methods"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cors_headers_are_sent_with_responses() -> Result<(), ServerError> {
+        let config = Configuration {
+            cors_expose_headers: vec!["Content-Length".to_string()],
+            ..Configuration::test_config()
+        };
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer("GET / HTTP/1.1", vec!["Origin:localhost"]),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, _) = split_response(&res);
+
+        assert!(head.contains("Access-Control-Allow-Origin:localhost"));
+        assert!(head.contains("Access-Control-Expose-Headers:Content-Length"));
+        assert!(head.contains("Vary:Origin"));
+
+        let config = Configuration {
+            allow_all_origins: true,
+            ..Configuration::test_config()
+        };
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer("GET / HTTP/1.1", vec!["Origin:https://example.com"]),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, _) = split_response(&res);
+
+        assert!(head.contains("Access-Control-Allow-Origin:*"));
+        assert!(!head.contains("Vary:Origin"));
+
+        // Rejected origins get a different answer than allowed ones as well.
+        let res = server::serve_request(
+            &Configuration::test_config(),
+            None,
+            create_test_buffer("GET / HTTP/1.1", vec!["Origin:https://evil.example"]),
+            &AppState::default(),
+        )?
+        .into_bytes()?;
+        let (head, _) = split_response(&res);
+
+        assert!(head.contains("Access-Control-Allow-Origin:null"));
+        assert!(head.contains("Vary:Origin"));
+
+        Ok(())
+    }
+
+    /// Serves a request against a temporary static directory containing `file.txt` with the
+    /// content "0123456789", last modified on Sun, 06 Nov 1994 08:49:37 GMT.
+    fn serve_file_request(headers: Vec<&str>) -> Result<Vec<u8>, ServerError> {
+        serve_file_request_with_content(b"0123456789", headers)
+    }
+
+    /// Same as `serve_file_request` but with a custom content for `file.txt`.
+    fn serve_file_request_with_content(
+        content: &[u8],
+        headers: Vec<&str>,
+    ) -> Result<Vec<u8>, ServerError> {
+        serve_files_request(
+            &[("file.txt", content)],
+            "GET /file.txt HTTP/1.1",
+            headers,
+            |_| {},
+        )
+    }
+
+    /// Serves a request against a temporary static directory containing `files`, all of them
+    /// last modified on Sun, 06 Nov 1994 08:49:37 GMT. `configure` may change the test config.
+    fn serve_files_request(
+        files: &[(&str, &[u8])],
+        info: &str,
+        headers: Vec<&str>,
+        configure: impl FnOnce(&mut Configuration),
+    ) -> Result<Vec<u8>, ServerError> {
+        // Every call gets its own directory since tests run in parallel.
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "vrs-file-request-test-{}-{}",
+            std::process::id(),
+            CALLS.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&root)?;
+
+        for (name, content) in files {
+            if let Some(parent) = root.join(name).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(root.join(name), content)?;
+            std::fs::File::options()
+                .write(true)
+                .open(root.join(name))?
+                .set_modified(UNIX_EPOCH + Duration::from_secs(784111777))?;
+        }
+
+        let mut config = Configuration {
+            absolute_static_content_path: root
+                .to_str()
+                .expect("Temp dir path should be valid UTF-8")
+                .to_string(),
+            ..Configuration::test_config()
+        };
+        configure(&mut config);
+
+        let res = server::serve_request(
+            &config,
+            None,
+            create_test_buffer(info, headers),
+            &AppState::new(&config),
+        )?;
+
+        Ok(res.into_bytes()?)
+    }
+
+    #[test]
+    fn single_range_is_partial_content() -> Result<(), ServerError> {
+        let res = serve_file_request(vec!["Origin:localhost", "Range:bytes=2-4"])?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 206);
+        assert!(head.contains("Accept-Ranges:bytes"));
+        assert!(head.contains("Content-Range:bytes 2-4/10"));
+        assert!(head.contains("Content-Length:3"));
+        assert_eq!(body, b"234");
+
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_ranges_are_multipart() -> Result<(), ServerError> {
+        let res = serve_file_request(vec!["Origin:localhost", "Range:bytes=0-1, -2"])?;
+        let (head, body) = split_response(&res);
+        let body = String::from_utf8_lossy(body);
+
+        assert_eq!(get_response_code(&head)?, 206);
+        let boundary = head
+            .split("multipart/byteranges; boundary=")
+            .nth(1)
+            .and_then(|rest| rest.lines().next())
+            .expect("missing multipart boundary");
+
+        assert!(body.starts_with(&format!("--{boundary}\r\n")));
+        assert!(body.contains("Content-Range: bytes 0-1/10\r\n\r\n01\r\n"));
+        assert!(body.contains("Content-Range: bytes 8-9/10\r\n\r\n89\r\n"));
+        assert!(body.ends_with(&format!("--{boundary}--\r\n")));
+        assert!(head.contains(&format!("Content-Length:{}", body.len())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsatisfiable_range_is_rejected() -> Result<(), ServerError> {
+        let res = serve_file_request(vec!["Origin:localhost", "Range:bytes=20-30"])?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 416);
+        assert!(head.contains("Content-Range:bytes */10"));
+        assert!(body.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn outdated_if_range_sends_full_content() -> Result<(), ServerError> {
+        let res = serve_file_request(vec![
+            "Origin:localhost",
+            "Range:bytes=2-4",
+            "If-Range:Sat, 05 Nov 1994 08:49:37 GMT",
+        ])?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert_eq!(body, b"0123456789");
+
+        Ok(())
+    }
+
+    /// Returns the value of the header `name` in the head of a response.
+    fn get_header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+        head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val)
+    }
+
+    #[test]
+    fn file_responses_have_validators() -> Result<(), ServerError> {
+        let res = serve_file_request(vec!["Origin:localhost"])?;
+        let (head, _) = split_response(&res);
+
+        assert!(get_header(&head, "ETag").is_some_and(|etag| etag.starts_with('"')));
+        assert_eq!(
+            get_header(&head, "Last-Modified"),
+            Some("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn matching_validators_are_not_modified() -> Result<(), ServerError> {
+        let res = serve_file_request(vec!["Origin:localhost"])?;
+        let (head, _) = split_response(&res);
+        let etag = get_header(&head, "ETag").expect("missing ETag").to_string();
+
+        let if_none_match = format!("If-None-Match:\"other\", {etag}");
+        let res = serve_file_request(vec!["Origin:localhost", &if_none_match])?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 304);
+        assert_eq!(get_header(&head, "ETag"), Some(etag.as_str()));
+        assert!(body.is_empty());
+
+        let res = serve_file_request(vec!["Origin:localhost", "If-None-Match:\"other\""])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 200);
+
+        let res = serve_file_request(vec![
+            "Origin:localhost",
+            "If-Modified-Since:Mon, 07 Nov 1994 00:00:00 GMT",
+        ])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 304);
+
+        let res = serve_file_request(vec![
+            "Origin:localhost",
+            "If-Modified-Since:Sat, 05 Nov 1994 00:00:00 GMT",
+        ])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 200);
+
+        let if_range = format!("If-Range:{etag}");
+        let res = serve_file_request(vec!["Origin:localhost", "Range:bytes=0-0", &if_range])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 206);
+
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range_dates_are_ignored() -> Result<(), ServerError> {
+        for header in ["If-Modified-Since", "If-Unmodified-Since"] {
+            let date = format!("{header}:Sun, 06 Nov 300000000000 08:49:37 GMT");
+            let res = serve_file_request(vec!["Origin:localhost", &date])?;
+            assert_eq!(get_response_code(&split_response(&res).0)?, 200);
+        }
+
+        let res = serve_file_request(vec![
+            "Origin:localhost",
+            "Range:bytes=0-0",
+            "If-Range:Sun, 06 Nov 300000000000 08:49:37 GMT",
+        ])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 200);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_preconditions_are_rejected() -> Result<(), ServerError> {
+        let res = serve_file_request(vec!["Origin:localhost", "If-Match:\"other\""])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 412);
+
+        let res = serve_file_request(vec![
+            "Origin:localhost",
+            "If-Unmodified-Since:Sat, 05 Nov 1994 00:00:00 GMT",
+        ])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 412);
+
+        let res = serve_file_request(vec!["Origin:localhost", "If-Match:*"])?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 200);
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_fallback_page_is_generated() -> Result<(), ServerError> {
+        let res = test_serve_request(Cursor::new(
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2000000\r\n\r\n".to_vec(),
+        ))?;
+        let (head, body) = split_response(res.as_bytes());
+        let body = String::from_utf8_lossy(body);
+
+        assert_eq!(get_response_code(&head)?, 413);
+        assert!(head.starts_with("HTTP/1.1 413 Content Too Large"));
+        assert!(body.contains("<h1>413 Content Too Large</h1>"));
+        assert!(head.contains(&format!("Content-Length:{}", body.len())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn encoded_paths_and_query_strings() -> Result<(), ServerError> {
+        let files: &[(&str, &[u8])] = &[("my file.html", b"spaces"), ("app.js", b"script")];
+
+        let res = serve_files_request(
+            files,
+            "GET /my%20file.html HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert_eq!(body, b"spaces");
+
+        // Cache-busting query strings don't change which file is served.
+        let res = serve_files_request(
+            files,
+            "GET /app.js?v=3 HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert_eq!(body, b"script");
+
+        let res = serve_files_request(
+            files,
+            "GET /my%2Ffile.html HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, _) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 400);
+
+        Ok(())
+    }
+
+    #[test]
+    fn directories_are_served_with_index_files() -> Result<(), ServerError> {
+        let files: &[(&str, &[u8])] = &[
+            ("docs/index.html", b"docs index"),
+            ("legacy/index.htm", b"legacy index"),
+            ("empty/file.txt", b"file"),
+        ];
+        let index_files = |config: &mut Configuration| {
+            config.index_files = vec!["index.html".to_string(), "index.htm".to_string()];
+        };
+
+        let res = serve_files_request(
+            files,
+            "GET /docs/ HTTP/1.1",
+            vec!["Origin:localhost"],
+            index_files,
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains("Content-Type:text/html"));
+        assert_eq!(body, b"docs index");
+
+        let res = serve_files_request(
+            files,
+            "GET /legacy/ HTTP/1.1",
+            vec!["Origin:localhost"],
+            index_files,
+        )?;
+        let (_, body) = split_response(&res);
+        assert_eq!(body, b"legacy index");
+
+        // Directories without an index file are listed.
+        let res = serve_files_request(
+            files,
+            "GET /empty/ HTTP/1.1",
+            vec!["Origin:localhost"],
+            index_files,
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(String::from_utf8_lossy(body).contains("<li>file.txt</li>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn directories_without_trailing_slash_are_redirected() -> Result<(), ServerError> {
+        let files: &[(&str, &[u8])] = &[("docs/index.html", b"docs index")];
+
+        let res = serve_files_request(
+            files,
+            "GET /docs HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 301);
+        assert_eq!(get_header(&head, "Location"), Some("/docs/"));
+        assert!(body.is_empty());
+
+        let res = serve_files_request(
+            files,
+            "GET /docs?page=2 HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, _) = split_response(&res);
+        assert_eq!(get_header(&head, "Location"), Some("/docs/?page=2"));
+
+        // `//docs/` would be a URL on the host `docs`.
+        let res = serve_files_request(
+            files,
+            "GET //docs HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, _) = split_response(&res);
+        assert_eq!(get_header(&head, "Location"), Some("/docs/"));
+
+        let res = serve_files_request(
+            &[("my docs/index.html", b"docs index")],
+            "GET /./my%20docs HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, _) = split_response(&res);
+        assert_eq!(get_header(&head, "Location"), Some("/my%20docs/"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn clean_urls_serve_html_files_without_extension() -> Result<(), ServerError> {
+        let files: &[(&str, &[u8])] = &[
+            ("about.html", b"about page"),
+            ("docs/index.html", b"docs index"),
+            ("notes.html", b"notes page"),
+            ("notes/todo.txt", b"todo"),
+        ];
+        let get = |target: &str, redirect: bool| {
+            serve_files_request(
+                files,
+                &format!("GET {target} HTTP/1.1"),
+                vec!["Origin:localhost"],
+                |config| {
+                    config.clean_urls = true;
+                    config.clean_urls_redirect = redirect;
+                },
+            )
+        };
+
+        let res = get("/about", false)?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains("Content-Type:text/html"));
+        assert_eq!(body, b"about page");
+
+        let res = get("/about.html", false)?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert_eq!(body, b"about page");
+
+        // Directories take precedence over HTML files.
+        let (head, _) = split_response(&get("/notes", false)?);
+        assert_eq!(get_response_code(&head)?, 301);
+        assert_eq!(get_header(&head, "Location"), Some("/notes/"));
+
+        let (head, _) = split_response(&get("/contact", false)?);
+        assert_eq!(get_response_code(&head)?, 404);
+
+        let res = get("/about.html?lang=en", true)?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 301);
+        assert_eq!(get_header(&head, "Location"), Some("/about?lang=en"));
+        assert!(body.is_empty());
+
+        let (head, _) = split_response(&get("/docs/index.html", true)?);
+        assert_eq!(get_response_code(&head)?, 301);
+        assert_eq!(get_header(&head, "Location"), Some("/docs/"));
+
+        // `//about` would be a URL on the host `about`.
+        let (head, _) = split_response(&get("//about.html", true)?);
+        assert_eq!(get_response_code(&head)?, 301);
+        assert_eq!(get_header(&head, "Location"), Some("/about"));
+
+        // `/notes` is the directory, so the file is only reachable with its extension.
+        let res = get("/notes.html", true)?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert_eq!(body, b"notes page");
+
+        // Without the option the extension is required.
+        let res = serve_files_request(
+            files,
+            "GET /about HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, _) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 404);
+
+        Ok(())
+    }
+
+    #[test]
+    fn spa_fallback_serves_index_for_unknown_paths() -> Result<(), ServerError> {
+        let files: &[(&str, &[u8])] = &[
+            ("index.html", b"app shell"),
+            ("assets/app.js", b"console.log(1)"),
+        ];
+        let configure = |config: &mut Configuration| {
+            config.spa_fallback = true;
+            config.spa_exclude_prefixes = vec!["/api/".to_string(), "/assets/".to_string()];
+        };
+
+        let get = |path: &str, configure: &dyn Fn(&mut Configuration)| {
+            serve_files_request(
+                files,
+                &format!("GET {path} HTTP/1.1"),
+                vec!["Origin:localhost"],
+                configure,
+            )
+        };
+
+        for path in ["/settings", "/users/42/profile?tab=2", "/missing.html"] {
+            let res = get(path, &configure)?;
+            let (head, body) = split_response(&res);
+            assert_eq!(get_response_code(&head)?, 200, "{path}");
+            assert!(head.contains("Content-Type:text/html"));
+            assert_eq!(body, b"app shell");
+        }
+
+        // Real files are served as they are.
+        let res = get("/assets/app.js", &configure)?;
+        let (_, body) = split_response(&res);
+        assert_eq!(body, b"console.log(1)");
+
+        for path in ["/api/users", "/assets/missing.js"] {
+            let (head, _) = split_response(&get(path, &configure)?);
+            assert_eq!(get_response_code(&head)?, 404, "{path}");
+        }
+
+        // Paths which aren't included aren't covered either.
+        let res = get("/settings", &|config: &mut Configuration| {
+            configure(config);
+            config.spa_include_prefixes = vec!["/app/".to_string()];
+        })?;
+        let (head, _) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 404);
+
+        let (head, _) = split_response(&get("/settings", &|_: &mut Configuration| {})?);
+        assert_eq!(get_response_code(&head)?, 404);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rules_redirect_and_rewrite_requests() -> Result<(), ServerError> {
+        let files: &[(&str, &[u8])] = &[("app/index.html", b"app shell")];
+        let configure = |config: &mut Configuration| {
+            config
+                .set("redirect", "/old/* /app/$1 302")
+                .expect("redirect should be valid");
+            config
+                .set("rewrite", "/app/* /app/index.html")
+                .expect("rewrite should be valid");
+        };
+
+        let res = serve_files_request(
+            files,
+            "GET /old/settings?tab=2 HTTP/1.1",
+            vec!["Origin:localhost"],
+            configure,
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 302);
+        assert_eq!(get_header(&head, "Location"), Some("/app/settings?tab=2"));
+        assert!(body.is_empty());
+
+        let res = serve_files_request(
+            files,
+            "GET /app/settings HTTP/1.1",
+            vec!["Origin:localhost"],
+            configure,
+        )?;
+        let (head, body) = split_response(&res);
+        assert_eq!(get_response_code(&head)?, 200);
+        assert_eq!(body, b"app shell");
+
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_response() -> Result<(), ServerError> {
+        let content = "Lorem ipsum dolor sit amet. ".repeat(100);
+
+        let res = serve_file_request_with_content(
+            content.as_bytes(),
+            vec!["Origin:localhost", "Accept-Encoding:deflate;q=0.5, gzip"],
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains("Content-Encoding:gzip"));
+        assert!(head.contains("Vary:Accept-Encoding"));
+        assert!(head.contains(&format!("Content-Length:{}", body.len())));
+        assert_eq!(body, vrs::compression::gzip(content.as_bytes()));
+
+        // Compressed and uncompressed responses are different representations.
+        let etag = get_header(&head, "ETag").expect("ETag should be set");
+        assert!(etag.ends_with("-gzip\""));
+
+        let res = serve_file_request_with_content(
+            content.as_bytes(),
+            vec!["Origin:localhost", &format!("If-None-Match:{etag}")],
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(!head.contains("Content-Encoding"));
+        assert!(head.contains("Vary:Accept-Encoding"));
+        assert_eq!(body, content.as_bytes());
+
+        // Files below the minimum size aren't compressed.
+        let res = serve_file_request(vec!["Origin:localhost", "Accept-Encoding:gzip"])?;
+        let (head, body) = split_response(&res);
+
+        assert!(!head.contains("Content-Encoding"));
+        assert!(!head.contains("Vary:Accept-Encoding"));
+        assert_eq!(body, b"0123456789");
+
+        Ok(())
+    }
+
+    #[test]
+    fn precompressed_files() -> Result<(), ServerError> {
+        let files: [(&str, &[u8]); 3] = [
+            ("app.js", b"console.log(1);"),
+            ("app.js.gz", b"gzip bytes"),
+            ("app.js.br", b"brotli bytes"),
+        ];
+
+        let res = serve_files_request(
+            &files,
+            "GET /app.js HTTP/1.1",
+            vec!["Origin:localhost", "Accept-Encoding:gzip, br"],
+            |_| {},
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains("Content-Type:text/javascript; charset=utf-8"));
+        assert!(head.contains("Content-Encoding:br"));
+        assert!(head.contains("Vary:Accept-Encoding"));
+        assert_eq!(body, b"brotli bytes");
+
+        let etag = get_header(&head, "ETag").expect("ETag should be set");
+        assert!(etag.ends_with("-br\""));
+
+        // Conditional and range requests apply to the precompressed file.
+        let res = serve_files_request(
+            &files,
+            "GET /app.js HTTP/1.1",
+            vec!["Origin:localhost", "Accept-Encoding:br", &format!("If-None-Match:{etag}")],
+            |_| {},
+        )?;
+        assert_eq!(get_response_code(&split_response(&res).0)?, 304);
+
+        let res = serve_files_request(
+            &files,
+            "GET /app.js HTTP/1.1",
+            vec!["Origin:localhost", "Accept-Encoding:gzip", "Range:bytes=0-3"],
+            |_| {},
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 206);
+        assert!(head.contains("Content-Encoding:gzip"));
+        assert!(head.contains("Content-Range:bytes 0-3/10"));
+        assert_eq!(body, b"gzip");
+
+        // Clients which don't accept any encoding get the original file.
+        let res = serve_files_request(
+            &files,
+            "GET /app.js HTTP/1.1",
+            vec!["Origin:localhost"],
+            |_| {},
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert!(!head.contains("Content-Encoding"));
+        assert!(head.contains("Vary:Accept-Encoding"));
+        assert_eq!(body, b"console.log(1);");
+
+        Ok(())
+    }
+
+    #[test]
+    fn large_files_are_streamed() -> Result<(), ServerError> {
+        let files: [(&str, &[u8]); 1] = [("file.txt", b"0123456789")];
+        let stream = |config: &mut Configuration| {
+            config.cache_files = true;
+            config.cache_max_file_size = 4;
+        };
+
+        let res = serve_files_request(
+            &files,
+            "GET /file.txt HTTP/1.1",
+            vec!["Origin:localhost"],
+            stream,
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert_eq!(get_response_code(&head)?, 200);
+        assert!(head.contains("Content-Length:10"));
+        assert!(head.contains("ETag:"));
+        assert_eq!(body, b"0123456789");
+
+        let res = serve_files_request(
+            &files,
+            "GET /file.txt HTTP/1.1",
+            vec!["Origin:localhost", "Range:bytes=1-2,-3"],
+            stream,
+        )?;
+        let (head, body) = split_response(&res);
+        let body = String::from_utf8_lossy(body);
+
+        assert_eq!(get_response_code(&head)?, 206);
+        assert!(head.contains(&format!("Content-Length:{}", body.len())));
+        assert!(body.contains("Content-Range: bytes 1-2/10\r\n\r\n12\r\n"));
+        assert!(body.contains("Content-Range: bytes 7-9/10\r\n\r\n789\r\n"));
+
+        let res = serve_files_request(
+            &files,
+            "HEAD /file.txt HTTP/1.1",
+            vec!["Origin:localhost"],
+            stream,
+        )?;
+        let (head, body) = split_response(&res);
+
+        assert!(head.contains("Content-Length:10"));
+        assert!(body.is_empty());
+
+        Ok(())
+    }
 }
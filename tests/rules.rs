@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use vrs::core::rules::{apply_rules, Rule, RuleAction};
+    use vrs::request::Request;
+    use vrs::status::StatusCode;
+
+    fn request(target: &str) -> Request {
+        Request::read_from(Cursor::new(
+            format!("GET {target} HTTP/1.1\r\nHost:localhost\r\n\r\n").into_bytes(),
+        ))
+        .expect("request should be valid")
+    }
+
+    fn redirect(status: StatusCode, location: &str) -> Option<RuleAction> {
+        Some(RuleAction::Redirect {
+            status,
+            location: location.to_string(),
+        })
+    }
+
+    #[test]
+    fn exact_patterns_only_match_the_same_path() -> Result<(), String> {
+        let rules = [Rule::parse_redirect("/old-page /new-page")?];
+
+        assert_eq!(
+            apply_rules(&rules, &request("/old-page")),
+            redirect(StatusCode::MovedPermanently, "/new-page")
+        );
+        assert_eq!(apply_rules(&rules, &request("/old-page/")), None);
+        assert_eq!(apply_rules(&rules, &request("/old-pages")), None);
+        assert_eq!(apply_rules(&rules, &request("/")), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn wildcards_are_substituted_into_the_target() -> Result<(), String> {
+        let rules = [
+            Rule::parse_redirect("/blog/* https://blog.example.com/$1 308")?,
+            Rule::parse_redirect("/releases/*/notes/*.html /changelog/$2/$1 302")?,
+        ];
+
+        assert_eq!(
+            apply_rules(&rules, &request("/blog/2024/hello-world")),
+            redirect(
+                StatusCode::PermanentRedirect,
+                "https://blog.example.com/2024/hello-world"
+            )
+        );
+        assert_eq!(
+            apply_rules(&rules, &request("/blog/")),
+            redirect(StatusCode::PermanentRedirect, "https://blog.example.com/")
+        );
+        assert_eq!(apply_rules(&rules, &request("/blog")), None);
+
+        assert_eq!(
+            apply_rules(&rules, &request("/releases/1.2/notes/fixes.html")),
+            redirect(StatusCode::Found, "/changelog/fixes/1.2")
+        );
+        assert_eq!(
+            apply_rules(&rules, &request("/releases/1.2/notes/fixes.txt")),
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn first_matching_rule_wins() -> Result<(), String> {
+        let rules = [
+            Rule::parse_redirect("/docs/legacy /docs/ 307")?,
+            Rule::parse_rewrite("/docs/* /manual/$1")?,
+        ];
+
+        assert_eq!(
+            apply_rules(&rules, &request("/docs/legacy")),
+            redirect(StatusCode::TemporaryRedirect, "/docs/")
+        );
+        assert_eq!(
+            apply_rules(&rules, &request("/docs/install.html")),
+            Some(RuleAction::Rewrite("/manual/install.html".to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn redirects_keep_the_query_unless_the_target_has_one() -> Result<(), String> {
+        let rules = [
+            Rule::parse_redirect("/search /find")?,
+            Rule::parse_redirect("/help /docs?section=help")?,
+            Rule::parse_rewrite("/app/* /app/index.html")?,
+        ];
+
+        assert_eq!(
+            apply_rules(&rules, &request("/search?q=vrs&page=2")),
+            redirect(StatusCode::MovedPermanently, "/find?q=vrs&page=2")
+        );
+        assert_eq!(
+            apply_rules(&rules, &request("/help?lang=en")),
+            redirect(StatusCode::MovedPermanently, "/docs?section=help")
+        );
+
+        // Only the path is matched.
+        assert_eq!(
+            apply_rules(&rules, &request("/app/settings?tab=2")),
+            Some(RuleAction::Rewrite("/app/index.html".to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn patterns_match_the_path_before_decoding() -> Result<(), String> {
+        let rules = [Rule::parse_redirect("/files/* /downloads/$1")?];
+
+        assert_eq!(
+            apply_rules(&rules, &request("/files/my%20file.txt")),
+            redirect(StatusCode::MovedPermanently, "/downloads/my%20file.txt")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn captures_cant_turn_paths_into_urls() -> Result<(), String> {
+        let rules = [
+            Rule::parse_redirect("/old/* /$1")?,
+            Rule::parse_rewrite("/app/* /$1")?,
+        ];
+
+        for (target, location) in [
+            ("/old//evil.com", "/evil.com"),
+            ("/old/%5Cevil.com", "/%5Cevil.com"),
+            ("/old/\\evil.com?a=1", "/evil.com?a=1"),
+            ("/old///evil.com/path", "/evil.com/path"),
+        ] {
+            assert_eq!(
+                apply_rules(&rules, &request(target)),
+                redirect(StatusCode::MovedPermanently, location),
+                "{target}"
+            );
+        }
+
+        assert_eq!(
+            apply_rules(&rules, &request("/app//index.html")),
+            Some(RuleAction::Rewrite("/index.html".to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let invalid_redirects = [
+            "/old",
+            "/old /new 301 extra",
+            "old /new",
+            "/old new",
+            "/old ftp://example.com/",
+            "/old /new 200",
+            "/old /new 404",
+            "/old /new abc",
+            "/old/* /new/$2",
+            "/old /new/$1",
+        ];
+
+        for rule in invalid_redirects {
+            assert!(Rule::parse_redirect(rule).is_err(), "{rule}");
+        }
+
+        let invalid_rewrites = [
+            "/old",
+            "/old /new 301",
+            "/old https://example.com/",
+            "/old /new?page=1",
+            "/old/* /new/$0",
+        ];
+
+        for rule in invalid_rewrites {
+            assert!(Rule::parse_rewrite(rule).is_err(), "{rule}");
+        }
+    }
+}
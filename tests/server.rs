@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use vrs::core::configuration::Configuration;
+    use vrs::core::server;
+    use vrs::error::ServerError;
+
+    /// Sends `request` to the server at `addr` and returns the whole response.
+    fn send_request(addr: SocketAddr, request: &str) -> Result<String, ServerError> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(request.as_bytes())?;
+
+        let mut res = String::new();
+        stream.read_to_string(&mut res)?;
+        Ok(res)
+    }
+
+    #[test]
+    fn multithreaded_server_uses_its_configuration() -> Result<(), ServerError> {
+        let root = std::env::temp_dir().join(format!("vrs-server-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root)?;
+        std::fs::write(root.join("page.html"), "served from the configured root")?;
+
+        let config = Configuration {
+            absolute_static_content_path: root
+                .to_str()
+                .expect("Temp dir path should be valid UTF-8")
+                .to_string(),
+            multithreading: true,
+            num_of_threads: 4,
+            use_security_headers: true,
+            append_extra_headers: true,
+            extra_headers: vec![("X-Served-By".to_string(), "vrs-test".to_string())],
+            ..Configuration::test_config()
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        // The server runs until the test process exits.
+        std::thread::spawn(move || server::serve_multithread(config, listener));
+
+        let requests: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    send_request(
+                        addr,
+                        "GET /page.html HTTP/1.1\r\nOrigin:localhost\r\nConnection:close\r\n\r\n",
+                    )
+                })
+            })
+            .collect();
+
+        for request in requests {
+            let res = request.join().expect("client thread panicked")?;
+
+            assert!(res.starts_with("HTTP/1.1 200 OK"));
+            assert!(res.contains("X-Served-By:vrs-test"));
+            assert!(res.contains("X-Content-Type-Options:nosniff"));
+            assert!(res.ends_with("served from the configured root"));
+        }
+
+        // The default static content directory isn't used.
+        let res = send_request(
+            addr,
+            "GET /favicon.ico HTTP/1.1\r\nOrigin:localhost\r\nConnection:close\r\n\r\n",
+        )?;
+        assert!(res.starts_with("HTTP/1.1 404 Not Found"));
+
+        std::fs::remove_dir_all(&root)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn singlethreaded_server_closes_connections() -> Result<(), ServerError> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        let config = Configuration {
+            keep_alive: true,
+            keep_alive_timeout: 30,
+            ..Configuration::test_config()
+        };
+
+        std::thread::spawn(move || server::serve_singlethread(config, listener));
+
+        // Neither client asks for the connection to be closed, an idle one would otherwise block
+        // the other one for the whole timeout.
+        let started = std::time::Instant::now();
+
+        for _ in 0..2 {
+            let res = send_request(addr, "GET / HTTP/1.1\r\nOrigin:localhost\r\n\r\n")?;
+            assert!(res.starts_with("HTTP/1.1 200 OK"));
+            assert!(res.contains("Connection:close"));
+        }
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        Ok(())
+    }
+}
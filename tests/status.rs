@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod tests {
+    use vrs::status::StatusCode;
+
+    #[test]
+    fn status_codes_round_trip() {
+        for status in StatusCode::ALL {
+            assert_eq!(StatusCode::from_u16(status.as_u16()), Some(status));
+            assert!(!status.reason_phrase().is_empty());
+        }
+
+        assert_eq!(StatusCode::from_u16(418), None);
+        assert_eq!(StatusCode::try_from(301), Ok(StatusCode::MovedPermanently));
+        assert_eq!(StatusCode::UriTooLong.to_string(), "414");
+        assert_eq!(StatusCode::UriTooLong.reason_phrase(), "URI Too Long");
+    }
+
+    #[test]
+    fn status_codes_are_classified() {
+        assert!(StatusCode::Continue.is_informational());
+        assert!(StatusCode::PartialContent.is_success());
+        assert!(StatusCode::PermanentRedirect.is_redirection());
+        assert!(StatusCode::NotFound.is_client_error());
+        assert!(StatusCode::ServiceUnavailable.is_server_error());
+        assert!(StatusCode::NotModified.forbids_body());
+        assert!(!StatusCode::NotFound.forbids_body());
+    }
+}
@@ -0,0 +1,19 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use vrs::thread::ThreadPool;
+
+    #[test]
+    fn workers_survive_panicking_jobs() -> std::io::Result<()> {
+        let pool = ThreadPool::new(1)?;
+        let (tx, rx) = mpsc::channel();
+
+        pool.execute(|| panic!("job failed"));
+        pool.execute(move || tx.send(()).expect("receiver should be alive"));
+
+        assert!(rx.recv_timeout(Duration::from_secs(10)).is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use vrs::core::uri::{clean_url, find_urn, html_file_for, percent_decode, percent_encode};
+
+    #[test]
+    fn paths_are_decoded_and_normalized() {
+        assert_eq!(find_urn("/"), Some("".to_string()));
+        assert_eq!(
+            find_urn("/my%20file.html"),
+            Some("my file.html".to_string())
+        );
+        assert_eq!(find_urn("/caf%C3%A9.html"), Some("café.html".to_string()));
+        assert_eq!(find_urn("/docs//./a.html"), Some("docs/a.html".to_string()));
+        assert_eq!(find_urn("/docs/../a.html"), Some("a.html".to_string()));
+        assert_eq!(find_urn("/docs/"), Some("docs/".to_string()));
+        assert_eq!(find_urn("/docs/%2E%2E/"), Some("".to_string()));
+    }
+
+    #[test]
+    fn invalid_paths_are_rejected() {
+        // Encoded slashes, NUL bytes, invalid UTF-8 and malformed escapes.
+        assert_eq!(find_urn("/a%2Fb.html"), None);
+        assert_eq!(find_urn("/a%2fb.html"), None);
+        assert_eq!(find_urn("/a%00.html"), None);
+        assert_eq!(find_urn("/%FF.html"), None);
+        assert_eq!(find_urn("/a%2.html"), None);
+        assert_eq!(find_urn("/a%+1.html"), None);
+
+        // Path traversal, also when encoded.
+        assert_eq!(find_urn("/../etc/passwd"), None);
+        assert_eq!(find_urn("/%2e%2e/etc/passwd"), None);
+        assert_eq!(find_urn("/docs/../../etc/passwd"), None);
+        assert_eq!(find_urn("relative"), None);
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("a%20b+c"), Some("a b+c".to_string()));
+        assert_eq!(percent_decode("100%"), None);
+    }
+
+    #[test]
+    fn percent_encoding() {
+        assert_eq!(percent_encode("docs/a-b_c.html"), "docs/a-b_c.html");
+        assert_eq!(percent_encode("my file?#%.html"), "my%20file%3F%23%25.html");
+        assert_eq!(percent_encode("café\\"), "caf%C3%A9%5C");
+        assert_eq!(
+            percent_decode(&percent_encode("a b/ü?")),
+            Some("a b/ü?".to_string())
+        );
+    }
+
+    #[test]
+    fn clean_urls() {
+        assert_eq!(html_file_for("about"), Some("about.html".to_string()));
+        assert_eq!(html_file_for("blog/post"), Some("blog/post.html".to_string()));
+        assert_eq!(html_file_for("blog/"), None);
+        assert_eq!(html_file_for(""), None);
+
+        assert_eq!(clean_url("/about.html"), Some("/about"));
+        assert_eq!(clean_url("blog/post.html"), Some("blog/post"));
+        assert_eq!(clean_url("/docs/index.html"), Some("/docs/"));
+        assert_eq!(clean_url("/index.html"), Some("/"));
+        assert_eq!(clean_url("index.html"), Some(""));
+        assert_eq!(clean_url("/reindex.html"), Some("/reindex"));
+        assert_eq!(clean_url("/about"), None);
+        assert_eq!(clean_url("/style.css"), None);
+        assert_eq!(clean_url("/docs/.html"), None);
+    }
+}